@@ -0,0 +1,173 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Ident, Type};
+
+/// Mark type with `#[must_use]` and derive `Dispose` for it.
+pub fn attribute(attr: TokenStream, input: DeriveInput) -> syn::Result<TokenStream> {
+    if !attr.is_empty() {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "`relevant::dispose` takes no arguments",
+        ));
+    }
+
+    let must_use = if input
+        .attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("must_use"))
+    {
+        quote!()
+    } else {
+        quote!(#[must_use])
+    };
+
+    Ok(quote! {
+        #must_use
+        #[derive(::relevant::Dispose)]
+        #input
+    })
+}
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+    let mut generics = input.generics.clone();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let (pattern, disposed) = deconstruct(&data.fields)?;
+            add_bounds(&mut generics, &disposed);
+            let statements = dispose_statements(&disposed);
+            quote! {
+                let #ident #pattern = self;
+                #(#statements)*
+            }
+        }
+        Data::Enum(data) => {
+            let mut arms = Vec::new();
+            for variant in &data.variants {
+                let variant_ident = &variant.ident;
+                let (pattern, disposed) = deconstruct(&variant.fields)?;
+                add_bounds(&mut generics, &disposed);
+                let statements = dispose_statements(&disposed);
+                arms.push(quote! {
+                    #ident::#variant_ident #pattern => {
+                        #(#statements)*
+                    }
+                });
+            }
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "`Dispose` can't be derived for unions",
+            ))
+        }
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::relevant::Dispose for #ident #ty_generics #where_clause {
+            fn dispose(self) {
+                #body
+            }
+        }
+    })
+}
+
+/// Field that must be disposed, bound to the identifier in the pattern.
+struct Disposed<'a> {
+    binding: Ident,
+    ty: &'a Type,
+}
+
+/// Build pattern that binds fields to be disposed and ignores others.
+fn deconstruct(fields: &Fields) -> syn::Result<(TokenStream, Vec<Disposed<'_>>)> {
+    let mut disposed = Vec::new();
+
+    let pattern = match fields {
+        Fields::Named(named) => {
+            let mut bindings = Vec::new();
+            for field in &named.named {
+                let ident = field.ident.as_ref().unwrap();
+                if is_disposed(field)? {
+                    let binding = format_ident!("__relevant_{}", ident);
+                    bindings.push(quote!(#ident: #binding));
+                    disposed.push(Disposed {
+                        binding,
+                        ty: &field.ty,
+                    });
+                } else {
+                    bindings.push(quote!(#ident: _));
+                }
+            }
+            quote!({ #(#bindings),* })
+        }
+        Fields::Unnamed(unnamed) => {
+            let mut bindings = Vec::new();
+            for (index, field) in unnamed.unnamed.iter().enumerate() {
+                if is_disposed(field)? {
+                    let binding = format_ident!("__relevant_{}", index);
+                    bindings.push(quote!(#binding));
+                    disposed.push(Disposed {
+                        binding,
+                        ty: &field.ty,
+                    });
+                } else {
+                    bindings.push(quote!(_));
+                }
+            }
+            quote!(( #(#bindings),* ))
+        }
+        Fields::Unit => quote!(),
+    };
+
+    Ok((pattern, disposed))
+}
+
+/// Check if field is marked with `#[dispose]` or has relevant type.
+fn is_disposed(field: &syn::Field) -> syn::Result<bool> {
+    for attr in &field.attrs {
+        if attr.path().is_ident("dispose") {
+            attr.meta.require_path_only()?;
+            return Ok(true);
+        }
+    }
+
+    match &field.ty {
+        Type::Path(path) if path.qself.is_none() => {
+            let last = path.path.segments.last().unwrap();
+            Ok(last.ident == "Relevant" || last.ident == "MustDispose")
+        }
+        _ => Ok(false),
+    }
+}
+
+fn add_bounds(generics: &mut syn::Generics, disposed: &[Disposed<'_>]) {
+    if generics.params.is_empty() {
+        return;
+    }
+
+    let where_clause = generics.make_where_clause();
+    for field in disposed {
+        let ty = field.ty;
+        where_clause
+            .predicates
+            .push(syn::parse_quote!(#ty: ::relevant::Dispose));
+    }
+}
+
+fn dispose_statements(disposed: &[Disposed<'_>]) -> Vec<TokenStream> {
+    disposed
+        .iter()
+        .map(|field| {
+            let binding = &field.binding;
+            quote!(::relevant::Dispose::dispose(#binding);)
+        })
+        .collect()
+}
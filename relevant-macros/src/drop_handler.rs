@@ -0,0 +1,32 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ItemFn;
+
+pub fn expand(attr: TokenStream, item: ItemFn) -> syn::Result<TokenStream> {
+    if !attr.is_empty() {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "`relevant::drop_handler` takes no arguments",
+        ));
+    }
+
+    if item.sig.inputs.len() != 1 {
+        return Err(syn::Error::new_spanned(
+            &item.sig,
+            "drop handler must take single `&relevant::DropReport` argument",
+        ));
+    }
+
+    let ident = &item.sig.ident;
+
+    Ok(quote! {
+        #item
+
+        const _: () = {
+            #[unsafe(no_mangle)]
+            fn __relevant_drop_handler(report: &::relevant::DropReport) {
+                #ident(report)
+            }
+        };
+    })
+}
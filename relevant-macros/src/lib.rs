@@ -0,0 +1,73 @@
+//! Procedural macros for `relevant` crate.
+//! Use them through `relevant` with "derive", "macros" and "test" features enabled.
+
+extern crate proc_macro;
+
+mod dispose;
+mod drop_handler;
+mod test;
+
+use proc_macro::TokenStream;
+
+/// Derive `relevant::Dispose` for struct or enum.
+///
+/// Generated `dispose` method deconstructs the value and disposes fields of type `Relevant`,
+/// `MustDispose` and fields marked with `#[dispose]` attribute.
+/// Type of marked fields must implement `Dispose` (e.g. derive it as well).
+/// All other fields are dropped.
+///
+/// Derive macros can't add attributes to the type,
+/// use `#[relevant::dispose]` attribute instead to also mark type with `#[must_use]`.
+#[proc_macro_derive(Dispose, attributes(dispose))]
+pub fn derive_dispose(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    dispose::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Mark struct or enum with `#[must_use]` and derive `relevant::Dispose` for it.
+/// Existing `#[must_use]` attribute is kept as is.
+///
+/// Requires "derive" feature of `relevant`.
+#[proc_macro_attribute]
+pub fn dispose(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item = syn::parse_macro_input!(item as syn::DeriveInput);
+    dispose::attribute(attr.into(), item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Mark function as test that fails if any `Relevant` value leaks.
+///
+/// Test fails with a full report if any value is dropped on the test thread,
+/// or if any value created on the test thread
+/// is still neither disposed nor dropped when test body returns.
+///
+/// Other tests running in parallel don't affect each other.
+/// Threads spawned by the test are tracked while they are in the test's scope,
+/// see `relevant::TestScope`.
+///
+/// Requires "test" feature of `relevant`.
+#[proc_macro_attribute]
+pub fn test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item = syn::parse_macro_input!(item as syn::ItemFn);
+    test::expand(attr.into(), item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Define function that handles dropped `Relevant` values
+/// when "extern-handler" feature is enabled.
+///
+/// Function must take single `&relevant::DropReport` argument.
+/// It must be defined exactly once in the final binary, like `#[panic_handler]`.
+/// Binary that enables "extern-handler" feature, directly or through a dependency,
+/// and doesn't define the handler fails to link with undefined `__relevant_drop_handler` symbol.
+#[proc_macro_attribute]
+pub fn drop_handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item = syn::parse_macro_input!(item as syn::ItemFn);
+    drop_handler::expand(attr.into(), item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,46 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{ItemFn, ReturnType};
+
+pub fn expand(attr: TokenStream, item: ItemFn) -> syn::Result<TokenStream> {
+    if !attr.is_empty() {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "`relevant::test` takes no arguments",
+        ));
+    }
+
+    if let Some(asyncness) = &item.sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "`relevant::test` doesn't support async functions",
+        ));
+    }
+
+    if !item.sig.inputs.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.sig.inputs,
+            "test functions can't take arguments",
+        ));
+    }
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+
+    let closure = match &sig.output {
+        ReturnType::Default => quote!(move || #block),
+        ReturnType::Type(_, ty) => quote!(move || -> #ty #block),
+    };
+
+    Ok(quote! {
+        #[::core::prelude::v1::test]
+        #(#attrs)*
+        #vis #sig {
+            ::relevant::__private::run_test(#closure)
+        }
+    })
+}
@@ -0,0 +1,161 @@
+#![deny(unused_attributes)]
+// Fields that are not disposed are only dropped.
+#![allow(dead_code)]
+
+use relevant::{capture, Dispose, MustDispose, Relevant};
+use std::{cell::Cell, rc::Rc};
+
+/// Field that is dropped, not disposed.
+struct Counter(Rc<Cell<usize>>);
+
+impl Drop for Counter {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+#[derive(Dispose)]
+struct Named {
+    relevant: Relevant,
+    must: MustDispose<u32>,
+    counter: Counter,
+}
+
+#[derive(Dispose)]
+struct Tuple(Relevant<u8>, Counter, relevant::Relevant<u16>);
+
+#[derive(Dispose)]
+struct Nested {
+    #[dispose]
+    named: Named,
+    #[dispose]
+    tuple: Tuple,
+}
+
+#[derive(Dispose)]
+struct Generic<T, U> {
+    #[dispose]
+    inner: T,
+    other: U,
+}
+
+#[derive(Dispose)]
+enum Enum<T> {
+    Unit,
+    Tuple(Relevant, Counter),
+    Struct {
+        #[dispose]
+        inner: T,
+        counter: Counter,
+    },
+}
+
+#[relevant::dispose]
+struct Attribute {
+    relevant: Relevant,
+}
+
+#[relevant::dispose]
+#[must_use = "keeps its own message"]
+enum AttributeWithMustUse {
+    Value(Relevant),
+}
+
+fn named(counter: &Rc<Cell<usize>>) -> Named {
+    Named {
+        relevant: Relevant::new(),
+        must: MustDispose::new(1),
+        counter: Counter(counter.clone()),
+    }
+}
+
+fn tuple(counter: &Rc<Cell<usize>>) -> Tuple {
+    Tuple(
+        Relevant::tagged(),
+        Counter(counter.clone()),
+        Relevant::tagged(),
+    )
+}
+
+#[test]
+fn disposes_named_and_tuple_fields() {
+    let counter = Rc::new(Cell::new(0));
+    let ((), reports) = capture(|| {
+        named(&counter).dispose();
+        tuple(&counter).dispose();
+    });
+    assert!(reports.is_empty());
+    assert_eq!(counter.get(), 2);
+}
+
+#[test]
+fn disposes_marked_fields() {
+    let counter = Rc::new(Cell::new(0));
+    let ((), reports) = capture(|| {
+        Nested {
+            named: named(&counter),
+            tuple: tuple(&counter),
+        }
+        .dispose()
+    });
+    assert!(reports.is_empty());
+    assert_eq!(counter.get(), 2);
+}
+
+#[test]
+fn disposes_generic_fields() {
+    let ((), reports) = capture(|| {
+        Generic {
+            inner: Relevant::new(),
+            other: 1u8,
+        }
+        .dispose();
+        Generic {
+            inner: MustDispose::new("value"),
+            other: Relevant::new().dispose(),
+        }
+        .dispose();
+    });
+    assert!(reports.is_empty());
+}
+
+#[test]
+fn disposes_enum_variants() {
+    let counter = Rc::new(Cell::new(0));
+    let ((), reports) = capture(|| {
+        Enum::<Relevant>::Unit.dispose();
+        Enum::<Relevant>::Tuple(Relevant::new(), Counter(counter.clone())).dispose();
+        Enum::Struct {
+            inner: named(&counter),
+            counter: Counter(counter.clone()),
+        }
+        .dispose();
+    });
+    assert!(reports.is_empty());
+    assert_eq!(counter.get(), 3);
+}
+
+#[test]
+fn drops_are_reported() {
+    let counter = Rc::new(Cell::new(0));
+    let ((), reports) = capture(|| {
+        drop(named(&counter));
+        drop(Enum::<Named>::Tuple(
+            Relevant::new(),
+            Counter(counter.clone()),
+        ));
+    });
+    assert_eq!(reports.len(), 3);
+}
+
+#[test]
+fn attribute_derives_dispose() {
+    let ((), reports) = capture(|| {
+        Attribute {
+            relevant: Relevant::new(),
+        }
+        .dispose();
+        AttributeWithMustUse::Value(Relevant::new()).dispose();
+    });
+    assert!(reports.is_empty());
+}
@@ -0,0 +1,6 @@
+#[relevant::dispose(must_use)]
+struct Foo {
+    relevant: relevant::Relevant,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[relevant::drop_handler(weak)]
+fn handler(report: &relevant::DropReport) {
+    let _ = report;
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use relevant::{Dispose, Relevant};
+
+#[derive(Dispose)]
+struct Foo {
+    relevant: Relevant,
+}
+
+#[derive(Dispose)]
+struct Bar {
+    #[dispose(deep)]
+    foo: Foo,
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+#![deny(unused_must_use)]
+
+use relevant::Relevant;
+
+#[relevant::dispose]
+struct Foo {
+    relevant: Relevant,
+}
+
+fn foo() -> Foo {
+    Foo {
+        relevant: Relevant::new(),
+    }
+}
+
+fn main() {
+    foo();
+}
@@ -0,0 +1,12 @@
+#[relevant::test(threads)]
+fn with_args() {}
+
+#[relevant::test]
+async fn async_test() {}
+
+#[relevant::test]
+fn with_inputs(value: u32) {
+    let _ = value;
+}
+
+fn main() {}
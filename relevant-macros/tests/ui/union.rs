@@ -0,0 +1,8 @@
+use relevant::Dispose;
+
+#[derive(Dispose)]
+union Union {
+    value: u32,
+}
+
+fn main() {}
@@ -0,0 +1,171 @@
+use report::DropReport;
+use std::cell::RefCell;
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<DropReport>>> = const { RefCell::new(None) };
+}
+
+/// Call the function collecting reports for `Relevant` values dropped on this thread
+/// instead of passing them to the drop handler.
+/// Returns function's result along with collected reports.
+///
+/// This allows tests to assert on leaks without changing crate features
+/// or installing a global drop handler.
+/// Captures can be nested, inner one takes reports while it is active.
+/// Values dropped while the thread is panicking, e.g. inside `catch_unwind`,
+/// are captured too instead of being queued for `take_unwind_drops`.
+pub fn capture<F, R>(f: F) -> (R, Vec<DropReport>)
+where
+    F: FnOnce() -> R,
+{
+    let mut restore = start();
+    let result = f();
+    let reports = restore.finish();
+    (result, reports)
+}
+
+/// Start capturing reports on this thread.
+/// Returned value stops capturing when finished or dropped.
+pub(crate) fn start() -> Restore {
+    let previous = CAPTURED.with(|captured| captured.replace(Some(Vec::new())));
+    Restore {
+        previous: Some(previous),
+    }
+}
+
+/// Returns `true` if capture is active on this thread.
+pub(crate) fn is_active() -> bool {
+    CAPTURED
+        .try_with(|captured| captured.borrow().is_some())
+        .unwrap_or(false)
+}
+
+/// Collect report if capture is active on this thread.
+/// Returns report back otherwise.
+pub(crate) fn collect(report: DropReport) -> Option<DropReport> {
+    let mut report = Some(report);
+    let _ = CAPTURED.try_with(|captured| {
+        if let Some(reports) = &mut *captured.borrow_mut() {
+            reports.extend(report.take());
+        }
+    });
+    report
+}
+
+/// Restores previous capture state even if function panics.
+pub(crate) struct Restore {
+    previous: Option<Option<Vec<DropReport>>>,
+}
+
+impl Restore {
+    /// Stop capturing and return collected reports.
+    pub(crate) fn finish(&mut self) -> Vec<DropReport> {
+        match self.previous.take() {
+            Some(previous) => CAPTURED
+                .with(|captured| captured.replace(previous))
+                .unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use policy::{set_category_policy, Policy};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use Relevant;
+
+    #[test]
+    fn nested_captures() {
+        let (inner, outer) = capture(|| {
+            let _outer = Relevant::new();
+            let ((), inner) = capture(|| {
+                let _inner = Relevant::new();
+            });
+            let _after = Relevant::new();
+            inner
+        });
+
+        assert_eq!(inner.len(), 1);
+        assert_eq!(outer.len(), 2);
+        assert!(!is_active());
+    }
+
+    #[test]
+    fn restores_state_on_panic() {
+        let ((), reports) = capture(|| {
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                capture(|| panic!("closure panicked"));
+            }));
+            assert!(result.is_err());
+
+            // Outer capture is active again.
+            assert!(is_active());
+            let _relevant = Relevant::new();
+        });
+
+        assert_eq!(reports.len(), 1);
+        assert!(!is_active());
+    }
+
+    #[test]
+    fn captures_counted_values() {
+        struct Counted;
+        set_category_policy::<Counted>(Some(Policy::Count));
+
+        let ((), reports) = capture(|| {
+            let _relevant = Relevant::<Counted>::tagged();
+        });
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].category(), Relevant::<Counted>::category());
+    }
+
+    #[test]
+    fn skips_ignored_values() {
+        struct Ignored;
+        set_category_policy::<Ignored>(Some(Policy::Ignore));
+
+        let ((), reports) = capture(|| {
+            let _relevant = Relevant::<Ignored>::tagged();
+        });
+
+        assert!(reports.is_empty());
+    }
+
+    #[cfg(feature = "suppress")]
+    #[test]
+    fn skips_suppressed_values() {
+        use suppress::{add_suppression, Suppression};
+
+        struct Suppressed;
+        add_suppression(Suppression::new().category("*::capture::tests::*::Suppressed"));
+
+        let ((), reports) = capture(|| {
+            let _relevant = Relevant::<Suppressed>::tagged();
+        });
+
+        assert!(reports.is_empty());
+    }
+
+    #[test]
+    fn captures_drops_during_unwinding() {
+        let (result, reports) = capture(|| {
+            catch_unwind(|| {
+                let _relevant = Relevant::new();
+                panic!("unwinding");
+            })
+        });
+
+        assert!(result.is_err());
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].panicking());
+    }
+}
@@ -0,0 +1,404 @@
+use limit::RateLimit;
+use policy::Policy;
+#[cfg(feature = "suppress")]
+use suppress::Suppression;
+
+use std::{collections::HashMap, env, ffi::OsStr, fs, sync::OnceLock, time::Duration};
+
+/// Which backtrace is captured for reports.
+#[cfg(feature = "backtrace")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Backtrace {
+    /// No backtraces.
+    Off,
+    /// Backtrace of the drop.
+    Drop,
+    /// Backtrace of the creation.
+    /// Falls back to `Drop` without "creation-backtrace" feature.
+    Creation,
+}
+
+/// Configuration read from the environment and the config file.
+#[derive(Debug, Default)]
+pub(crate) struct Config {
+    /// Policy that replaces the default one.
+    pub(crate) policy: Option<Policy>,
+
+    /// Backtrace mode that replaces the one selected by features.
+    #[cfg(feature = "backtrace")]
+    pub(crate) backtrace: Option<Backtrace>,
+
+    /// Limit for reports of values created at the same site.
+    pub(crate) rate_limit: Option<RateLimit>,
+
+    /// Known leaks that are not reported.
+    #[cfg(feature = "suppress")]
+    pub(crate) suppressions: Vec<Suppression>,
+
+    /// Policies for specific categories.
+    categories: HashMap<String, Policy>,
+}
+
+impl Config {
+    /// Returns policy configured for the category.
+    pub(crate) fn category(&self, category: &str) -> Option<Policy> {
+        self.categories.get(category).copied()
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Returns configuration, reading it on first call.
+pub(crate) fn config() -> &'static Config {
+    CONFIG.get_or_init(load)
+}
+
+fn load() -> Config {
+    let table = env::var_os("RELEVANT_CONFIG").and_then(|path| read_file(&path));
+    configure(table.as_ref(), |name| env::var(name).ok())
+}
+
+fn read_file(path: &OsStr) -> Option<toml::Table> {
+    match fs::read_to_string(path) {
+        Ok(content) => match content.parse::<toml::Table>() {
+            Ok(table) => Some(table),
+            Err(err) => {
+                eprintln!("relevant: failed to parse config file {:?}: {}", path, err);
+                None
+            }
+        },
+        Err(err) => {
+            eprintln!("relevant: failed to read config file {:?}: {}", path, err);
+            None
+        }
+    }
+}
+
+/// Build configuration from the config file and environment variables looked up with `var`.
+fn configure(table: Option<&toml::Table>, var: impl Fn(&str) -> Option<String>) -> Config {
+    let mut config = Config::default();
+
+    if let Some(table) = table {
+        read_table(&mut config, table);
+    }
+
+    // Environment takes precedence over the file.
+    if let Some(value) = var("RELEVANT_POLICY") {
+        config.policy = parse_policy("RELEVANT_POLICY", &value).or(config.policy);
+    }
+
+    #[cfg(feature = "backtrace")]
+    {
+        if let Some(value) = var("RELEVANT_BACKTRACE") {
+            config.backtrace = parse_backtrace("RELEVANT_BACKTRACE", &value).or(config.backtrace);
+        }
+    }
+
+    config
+}
+
+fn read_table(config: &mut Config, table: &toml::Table) {
+    for (key, value) in table {
+        match (&key[..], value) {
+            ("policy", value) => {
+                config.policy =
+                    string("policy", value).and_then(|value| parse_policy("policy", &value));
+            }
+            #[cfg(feature = "backtrace")]
+            ("backtrace", value) => {
+                config.backtrace = string("backtrace", value)
+                    .and_then(|value| parse_backtrace("backtrace", &value));
+            }
+            #[cfg(not(feature = "backtrace"))]
+            ("backtrace", _) => {}
+            ("categories", toml::Value::Table(categories)) => {
+                for (category, value) in categories {
+                    let key = format!("categories.{}", category);
+                    if let Some(policy) =
+                        string(&key, value).and_then(|value| parse_policy(&key, &value))
+                    {
+                        config.categories.insert(category.clone(), policy);
+                    }
+                }
+            }
+            ("rate-limit", toml::Value::Table(limit)) => {
+                config.rate_limit = read_rate_limit(limit);
+            }
+            #[cfg(feature = "suppress")]
+            ("suppress", toml::Value::Array(suppressions)) => {
+                for (index, value) in suppressions.iter().enumerate() {
+                    match value {
+                        toml::Value::Table(table) => {
+                            if let Some(suppression) = read_suppression(index, table) {
+                                config.suppressions.push(suppression);
+                            }
+                        }
+                        _ => eprintln!("relevant: invalid suppress[{}] ignored", index),
+                    }
+                }
+            }
+            _ => eprintln!("relevant: unknown config key `{}` ignored", key),
+        }
+    }
+}
+
+/// Reads `burst` and `window` in seconds.
+/// Missing values default to 10 reports per second.
+fn read_rate_limit(table: &toml::Table) -> Option<RateLimit> {
+    let mut limit = RateLimit {
+        burst: 10,
+        window: Duration::from_secs(1),
+    };
+    for (key, value) in table {
+        match (&key[..], value) {
+            ("burst", toml::Value::Integer(burst)) if *burst >= 0 => {
+                limit.burst = *burst as usize;
+            }
+            ("window", toml::Value::Integer(secs)) if *secs >= 0 => {
+                limit.window = Duration::from_secs(*secs as u64);
+            }
+            ("window", toml::Value::Float(secs)) if *secs >= 0.0 => {
+                limit.window = Duration::from_secs_f64(*secs);
+            }
+            _ => {
+                eprintln!("relevant: invalid rate-limit.{} ignored", key);
+                return None;
+            }
+        }
+    }
+    Some(limit)
+}
+
+/// Reads `category`, `location` and `message` patterns.
+#[cfg(feature = "suppress")]
+fn read_suppression(index: usize, table: &toml::Table) -> Option<Suppression> {
+    let mut suppression = Suppression::new();
+    for (key, value) in table {
+        suppression = match (&key[..], value) {
+            ("category", toml::Value::String(pattern)) => suppression.category(&pattern[..]),
+            ("location", toml::Value::String(pattern)) => suppression.location(&pattern[..]),
+            ("message", toml::Value::String(pattern)) => match suppression.message(pattern) {
+                Ok(suppression) => suppression,
+                Err(err) => {
+                    eprintln!(
+                        "relevant: invalid suppress[{}].message ignored: {}",
+                        index, err
+                    );
+                    return None;
+                }
+            },
+            _ => {
+                eprintln!("relevant: invalid suppress[{}].{} ignored", index, key);
+                return None;
+            }
+        };
+    }
+    Some(suppression)
+}
+
+/// Accepts strings and, for convenience, integers and booleans.
+fn string(key: &str, value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(value) => Some(value.clone()),
+        toml::Value::Integer(value) => Some(value.to_string()),
+        toml::Value::Boolean(value) => Some(if *value { "1" } else { "0" }.to_owned()),
+        _ => {
+            eprintln!(
+                "relevant: unexpected {} value for {} ignored",
+                value.type_str(),
+                key
+            );
+            None
+        }
+    }
+}
+
+fn parse_policy(key: &str, value: &str) -> Option<Policy> {
+    let policy = match &value.trim().to_ascii_lowercase()[..] {
+        "report" => Policy::Report,
+        "count" => Policy::Count,
+        "abort" => Policy::Abort,
+        "panic" => Policy::Panic,
+        "log" => Policy::Log,
+        "stderr" | "eprint" => Policy::Eprint,
+        "ignore" => Policy::Ignore,
+        _ => {
+            eprintln!("relevant: unknown policy `{}` for {} ignored", value, key);
+            return None;
+        }
+    };
+    Some(policy)
+}
+
+#[cfg(feature = "backtrace")]
+fn parse_backtrace(key: &str, value: &str) -> Option<Backtrace> {
+    let backtrace = match &value.trim().to_ascii_lowercase()[..] {
+        "0" | "off" => Backtrace::Off,
+        "1" | "drop" => Backtrace::Drop,
+        "creation" => Backtrace::Creation,
+        _ => {
+            eprintln!(
+                "relevant: unknown backtrace mode `{}` for {} ignored",
+                value, key
+            );
+            return None;
+        }
+    };
+    Some(backtrace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Config {
+        configure(Some(&content.parse().unwrap()), |_| None)
+    }
+
+    #[test]
+    fn reads_file() {
+        let config = parse(
+            r#"
+            policy = "count"
+
+            [categories]
+            "my::Texture" = "panic"
+            "my::Buffer" = "stderr"
+
+            [rate-limit]
+            burst = 3
+            window = 0.5
+            "#,
+        );
+
+        assert_eq!(config.policy, Some(Policy::Count));
+        assert_eq!(config.category("my::Texture"), Some(Policy::Panic));
+        assert_eq!(config.category("my::Buffer"), Some(Policy::Eprint));
+        assert_eq!(config.category("my::Fence"), None);
+        assert_eq!(
+            config.rate_limit,
+            Some(RateLimit {
+                burst: 3,
+                window: Duration::from_millis(500),
+            })
+        );
+    }
+
+    #[test]
+    fn rate_limit_defaults() {
+        let config = parse("[rate-limit]");
+        assert_eq!(
+            config.rate_limit,
+            Some(RateLimit {
+                burst: 10,
+                window: Duration::from_secs(1),
+            })
+        );
+    }
+
+    #[cfg(feature = "backtrace")]
+    #[test]
+    fn reads_backtrace() {
+        assert_eq!(parse("backtrace = 0").backtrace, Some(Backtrace::Off));
+        assert_eq!(parse("backtrace = true").backtrace, Some(Backtrace::Drop));
+        assert_eq!(
+            parse(r#"backtrace = "Creation""#).backtrace,
+            Some(Backtrace::Creation)
+        );
+        assert_eq!(parse(r#"backtrace = "full""#).backtrace, None);
+    }
+
+    #[cfg(feature = "suppress")]
+    #[test]
+    fn reads_suppressions() {
+        let config = parse(
+            r#"
+            [[suppress]]
+            category = "third_party::*"
+
+            [[suppress]]
+            message = "("
+
+            [[suppress]]
+            category = "my::*"
+            unknown = "x"
+            "#,
+        );
+
+        // Invalid suppressions are skipped entirely.
+        assert_eq!(config.suppressions.len(), 1);
+    }
+
+    #[test]
+    fn ignores_invalid_keys() {
+        let config = parse(
+            r#"
+            unknown = 1
+            policy = "sometimes"
+            categories = "report"
+
+            [rate-limit]
+            burst = -1
+            "#,
+        );
+
+        assert_eq!(config.policy, None);
+        assert!(config.categories.is_empty());
+        assert_eq!(config.rate_limit, None);
+
+        let config = parse(
+            r#"
+            policy = ["report"]
+            unknown = "report"
+
+            [categories]
+            "my::Texture" = "abort"
+            "my::Buffer" = "nothing"
+
+            [rate-limit]
+            burst = 1
+            windows = 2
+            "#,
+        );
+
+        assert_eq!(config.policy, None);
+        assert_eq!(config.category("my::Texture"), Some(Policy::Abort));
+        assert_eq!(config.category("my::Buffer"), None);
+        assert_eq!(config.rate_limit, None);
+    }
+
+    #[test]
+    fn environment_overrides_file() {
+        let table = r#"policy = "count""#.parse().unwrap();
+
+        let config = configure(Some(&table), |name| match name {
+            "RELEVANT_POLICY" => Some("Ignore".to_owned()),
+            _ => None,
+        });
+        assert_eq!(config.policy, Some(Policy::Ignore));
+
+        // Invalid value doesn't override the file.
+        let config = configure(Some(&table), |name| match name {
+            "RELEVANT_POLICY" => Some("sometimes".to_owned()),
+            _ => None,
+        });
+        assert_eq!(config.policy, Some(Policy::Count));
+
+        let config = configure(None, |name| match name {
+            "RELEVANT_POLICY" => Some("log".to_owned()),
+            _ => None,
+        });
+        assert_eq!(config.policy, Some(Policy::Log));
+    }
+
+    #[cfg(feature = "backtrace")]
+    #[test]
+    fn environment_overrides_backtrace() {
+        let table = r#"backtrace = "creation""#.parse().unwrap();
+        let config = configure(Some(&table), |name| match name {
+            "RELEVANT_BACKTRACE" => Some("off".to_owned()),
+            _ => None,
+        });
+        assert_eq!(config.backtrace, Some(Backtrace::Off));
+    }
+}
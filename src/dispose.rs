@@ -0,0 +1,22 @@
+use {MustDispose, Relevant};
+
+/// Values that can't be automatically dropped and must be disposed explicitly.
+///
+/// With "derive" feature this trait can be derived for structs and enums
+/// that contain `Relevant` fields.
+pub trait Dispose {
+    /// Dispose this value.
+    fn dispose(self);
+}
+
+impl<T: ?Sized> Dispose for Relevant<T> {
+    fn dispose(self) {
+        Relevant::dispose(self)
+    }
+}
+
+impl<T> Dispose for MustDispose<T> {
+    fn dispose(self) {
+        self.into_inner();
+    }
+}
@@ -0,0 +1,218 @@
+use policy::Policy;
+use report::DropReport;
+use std::{
+    mem, ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+use sync;
+
+#[cfg(feature = "std")]
+use capture;
+
+#[cfg(feature = "std")]
+use report::RelevantDropped;
+
+#[cfg(all(
+    feature = "tracing",
+    not(feature = "panic"),
+    not(feature = "extern-handler")
+))]
+use trace;
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "extern-handler")] {
+        extern "Rust" {
+            /// Defined in the final binary with `#[relevant::drop_handler]` attribute.
+            /// Stable Rust has no weak linkage, so there is no default definition
+            /// and binary without the handler fails to link.
+            fn __relevant_drop_handler(report: &DropReport);
+        }
+
+        fn sink(report: &DropReport) {
+            unsafe { __relevant_drop_handler(report) }
+        }
+    } else if #[cfg(feature = "panic")] {
+        fn sink(report: &DropReport) {
+            panic(report)
+        }
+    } else if #[cfg(feature = "tracing")] {
+        fn sink(report: &DropReport) {
+            trace::emit(report)
+        }
+    } else if #[cfg(feature = "log")] {
+        fn sink(report: &DropReport) {
+            log(report)
+        }
+    } else if #[cfg(feature = "defmt")] {
+        fn sink(report: &DropReport) {
+            defmt::error!("{}", report)
+        }
+    } else if #[cfg(feature = "std")] {
+        fn sink(report: &DropReport) {
+            eprint(report)
+        }
+    } else {
+        fn sink(report: &DropReport) {
+            panic(report)
+        }
+    }
+}
+
+/// Function that is called for each dropped `Relevant` value.
+pub type DropHandler = fn(&DropReport);
+
+/// Installed handler. Null pointer stands for `default_drop_handler`.
+static HANDLER: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Install handler that will be called for each dropped `Relevant` value.
+/// Returns previously installed handler.
+///
+/// Handler may be replaced at any time, e.g. to panic in tests,
+/// log in staging and silently count leaks in production.
+pub fn set_drop_handler(handler: DropHandler) -> DropHandler {
+    from_ptr(sync::swap_ptr(&HANDLER, handler as *mut ()))
+}
+
+/// Returns currently installed drop handler.
+pub fn drop_handler() -> DropHandler {
+    from_ptr(HANDLER.load(Ordering::Acquire))
+}
+
+/// Handler used unless another one is installed with `set_drop_handler`.
+///
+/// Reports with mechanism selected by crate features:
+/// * "extern-handler" feature makes it call function defined in the final binary
+///   with `#[relevant::drop_handler]` attribute.
+/// * "panic" feature makes it panic with `RelevantDropped` payload.
+/// * "tracing" feature makes it emit `tracing::error!` event with structured fields.
+/// * "log" feature makes it emit `log::error!`.
+/// * "defmt" feature makes it emit `defmt::error!`.
+/// * otherwise it prints into stderr using `eprintln!`.
+///
+/// Without "std" feature and other mechanisms enabled it panics.
+pub fn default_drop_handler(report: &DropReport) {
+    sink(report)
+}
+
+/// Returns `true` if reports passed to the drop handler end up panicking.
+#[cfg(feature = "std")]
+pub(crate) fn report_panics() -> bool {
+    cfg!(all(feature = "panic", not(feature = "extern-handler")))
+        && HANDLER.load(Ordering::Acquire).is_null()
+}
+
+/// Returns `true` if reports are captured on this thread.
+pub(crate) fn capturing() -> bool {
+    #[cfg(feature = "std")]
+    {
+        capture::is_active()
+    }
+
+    #[cfg(not(feature = "std"))]
+    {
+        false
+    }
+}
+
+/// Pass report to the active capture or handle it according to the policy.
+pub(crate) fn dispatch(report: DropReport, policy: Policy) {
+    #[cfg(feature = "std")]
+    let report = match capture::collect(report) {
+        Some(report) => report,
+        None => return,
+    };
+
+    match policy {
+        Policy::Report => drop_handler()(&report),
+        Policy::Count | Policy::Ignore => {}
+        Policy::Abort => abort(&report),
+        Policy::Panic => panic(&report),
+        Policy::Log => {
+            #[cfg(feature = "log")]
+            log(&report);
+
+            #[cfg(not(feature = "log"))]
+            drop_handler()(&report);
+        }
+        Policy::Eprint => {
+            #[cfg(feature = "std")]
+            eprint(&report);
+
+            #[cfg(not(feature = "std"))]
+            drop_handler()(&report);
+        }
+    }
+}
+
+/// Pass report to the installed drop handler and abort.
+#[cfg(feature = "std")]
+fn abort(report: &DropReport) -> ! {
+    // Handler must not unwind past the leaked value.
+    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop_handler()(report)));
+    std::process::abort()
+}
+
+/// Pass report to the installed drop handler and panic.
+#[cfg(not(feature = "std"))]
+fn abort(report: &DropReport) -> ! {
+    drop_handler()(report);
+    panic!("Values of this type can't be dropped! Aborting")
+}
+
+/// Panic with `RelevantDropped` payload.
+/// Nothing is printed here, panic hook decides whether and how the report is shown.
+#[cfg(feature = "std")]
+fn panic(report: &DropReport) -> ! {
+    std::panic::panic_any(RelevantDropped::new(report.clone()))
+}
+
+#[cfg(not(feature = "std"))]
+fn panic(report: &DropReport) -> ! {
+    panic!("{}", report)
+}
+
+#[cfg(feature = "log")]
+fn log(report: &DropReport) {
+    log::error!(target: "relevant", "{}", report)
+}
+
+#[cfg(feature = "std")]
+fn eprint(report: &DropReport) {
+    eprintln!("{}", report)
+}
+
+fn from_ptr(ptr: *mut ()) -> DropHandler {
+    if ptr.is_null() {
+        default_drop_handler
+    } else {
+        // Only `DropHandler` values are ever stored in `HANDLER`.
+        unsafe { mem::transmute::<*mut (), DropHandler>(ptr) }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use policy::{set_category_policy, Policy};
+    use std::panic::catch_unwind;
+    use Relevant;
+
+    #[test]
+    fn panic_payload_is_relevant_dropped() {
+        struct Panicking;
+        set_category_policy::<Panicking>(Some(Policy::Panic));
+
+        let payload = catch_unwind(|| {
+            let _relevant = Relevant::<Panicking>::tagged();
+        })
+        .unwrap_err();
+
+        let dropped = payload
+            .downcast_ref::<RelevantDropped>()
+            .expect("`RelevantDropped` payload");
+        assert_eq!(
+            dropped.report().category(),
+            Relevant::<Panicking>::category()
+        );
+    }
+}
@@ -0,0 +1,197 @@
+#[cfg(all(feature = "config", feature = "creation-backtrace"))]
+use config;
+use policy::{self, Policy};
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+#[cfg(all(feature = "message", feature = "std"))]
+use std::borrow::Cow;
+
+#[cfg(feature = "location")]
+use std::panic::Location;
+
+#[cfg(feature = "registry")]
+use registry;
+
+#[cfg(all(feature = "id", target_has_atomic = "64"))]
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+
+#[cfg(all(feature = "id", not(target_has_atomic = "64")))]
+use {std::sync::atomic::AtomicUsize, sync};
+
+/// Message attached to `Relevant` value.
+#[cfg(all(feature = "message", feature = "std"))]
+pub(crate) type Message = Cow<'static, str>;
+
+/// Message attached to `Relevant` value.
+#[cfg(all(feature = "message", not(feature = "std")))]
+pub(crate) type Message = &'static str;
+
+/// Information collected for `Relevant` value to be reported if value is dropped.
+///
+/// It doesn't participate in comparison and hashing,
+/// all `Relevant` values are equal to each other, even with different ids.
+#[derive(Debug)]
+pub(crate) struct Info {
+    #[cfg(feature = "message")]
+    pub(crate) message: Option<Message>,
+
+    #[cfg(feature = "location")]
+    pub(crate) location: &'static Location<'static>,
+
+    /// Unresolved backtrace captured on creation.
+    #[cfg(feature = "creation-backtrace")]
+    pub(crate) backtrace: Option<backtrace::Backtrace>,
+
+    /// Unique id of the value.
+    #[cfg(feature = "id")]
+    pub(crate) id: u64,
+
+    /// Span that was active when value was created.
+    #[cfg(feature = "tracing")]
+    pub(crate) span: Option<tracing::Span>,
+
+    /// Policy that overrides global one.
+    #[cfg(feature = "policy")]
+    pub(crate) policy: Option<Policy>,
+
+    /// Raw handle of the guarded resource.
+    #[cfg(feature = "handle")]
+    pub(crate) handle: Option<u64>,
+}
+
+impl Info {
+    /// Collect information about value being created by the caller.
+    #[track_caller]
+    pub(crate) fn new() -> Self {
+        Info {
+            #[cfg(feature = "message")]
+            message: None,
+
+            #[cfg(feature = "location")]
+            location: Location::caller(),
+
+            #[cfg(feature = "creation-backtrace")]
+            backtrace: creation_backtrace(),
+
+            #[cfg(feature = "id")]
+            id: next_id(),
+
+            #[cfg(feature = "tracing")]
+            span: Some(tracing::Span::current()),
+
+            #[cfg(feature = "policy")]
+            policy: None,
+
+            #[cfg(feature = "handle")]
+            handle: None,
+        }
+    }
+
+    /// Returns policy for this value.
+    /// Value's own policy takes precedence over one configured for the category,
+    /// which takes precedence over global policy.
+    pub(crate) fn policy(&self, category: &str) -> Policy {
+        #[cfg(feature = "policy")]
+        {
+            if let Some(policy) = self.policy {
+                return policy;
+            }
+        }
+
+        policy::resolve(category)
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(feature = "id", target_has_atomic = "64"))] {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        fn next_id() -> u64 {
+            NEXT_ID.fetch_add(1, AtomicOrdering::Relaxed)
+        }
+    } else if #[cfg(feature = "id")] {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        fn next_id() -> u64 {
+            sync::increment(&NEXT_ID) as u64
+        }
+    }
+}
+
+/// Capture unresolved backtrace unless disabled by configuration.
+#[cfg(feature = "creation-backtrace")]
+fn creation_backtrace() -> Option<backtrace::Backtrace> {
+    #[cfg(feature = "config")]
+    {
+        match config::config().backtrace {
+            None | Some(config::Backtrace::Creation) => {}
+            Some(_) => return None,
+        }
+    }
+    Some(backtrace::Backtrace::new_unresolved())
+}
+
+impl Clone for Info {
+    /// Clone is a new obligation and gets its own id.
+    // `Message` is `Copy` without "std" feature.
+    #[allow(clippy::clone_on_copy)]
+    fn clone(&self) -> Self {
+        Info {
+            #[cfg(feature = "message")]
+            message: self.message.clone(),
+
+            #[cfg(feature = "location")]
+            location: self.location,
+
+            #[cfg(feature = "creation-backtrace")]
+            backtrace: self.backtrace.clone(),
+
+            #[cfg(feature = "id")]
+            id: next_id(),
+
+            #[cfg(feature = "tracing")]
+            span: self.span.clone(),
+
+            #[cfg(feature = "policy")]
+            policy: self.policy,
+
+            #[cfg(feature = "handle")]
+            handle: self.handle,
+        }
+    }
+}
+
+#[cfg(feature = "registry")]
+impl Drop for Info {
+    /// Both disposing and dropping `Relevant` value fulfills the obligation.
+    fn drop(&mut self) {
+        registry::unregister(self.id)
+    }
+}
+
+impl PartialEq for Info {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Info {}
+
+impl PartialOrd for Info {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Info {
+    fn cmp(&self, _: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl Hash for Info {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
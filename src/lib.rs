@@ -1,12 +1,54 @@
 //! Defines `Relevant` type to use in types that requires
 //! custom destruction.
-//! Crate supports 3 main mechnisms for error reporting:
-//! * "panic" feature makes `Relevant` panic on drop
+//! Crate supports 6 main mechnisms for error reporting:
+//! * "extern-handler" feature makes `Relevant` call function defined in the final binary
+//!   with `#[relevant::drop_handler]` attribute on drop. Useful for "no_std" targets.
+//!   There is no fallback: binary that doesn't define the handler fails to link.
+//!   Features are unified across dependency graph, so only the final binary should enable it,
+//!   never a library.
+//! * "panic" feature makes `Relevant` panic on drop.
+//!   With "std" feature the panic payload is `RelevantDropped`.
+//! * "tracing" feature uses `tracing` crate and `Relevant` will emit `tracing::error!` event on drop.
+//!   Span active when value was created is recorded and becomes the parent of the event.
 //! * "log" feature uses `log` crate and `Relevant` will emit `log::error!` on drop.
-//! * otherwise `Relevant` will print into stderr using `eprintln!` on drop.
+//! * "defmt" feature uses `defmt` crate and `Relevant` will emit `defmt::error!` on drop.
+//!   Suitable for embedded targets without "std" feature.
+//! * otherwise `Relevant` will print into stderr using `eprintln!` on drop,
+//!   or panic without "std" feature.
+//!
+//! Features above select `default_drop_handler`.
+//! Another handler can be installed in runtime with `set_drop_handler`.
+//!
+//! "count" feature makes `Relevant` only increment counters on drop, see `stats`.
+//! "abort" feature makes `Relevant` abort the process after reporting,
+//! even if it is dropped while panicking.
+//! Policy can be changed in runtime with `set_policy`.
+//! "policy" feature allows overriding policy for individual values, see `Relevant::with_policy`.
+//! "config" feature reads policy and backtrace mode from `RELEVANT_POLICY` and `RELEVANT_BACKTRACE`
+//! environment variables and from TOML file pointed by `RELEVANT_CONFIG`,
+//! which may also set policies for individual categories.
+//!
+//! With "std" feature reports of values created at the same site can be rate limited,
+//! suppressed drops are summarized. See `set_rate_limit` and `report_suppressed`.
+//!
+//! Values dropped while thread is panicking are not reported immediately,
+//! unless reports are captured. See `take_unwind_drops` and `report_unwind_drops`.
 //!
 //! "backtrace" feature will add backtrace to the error unless it is reported via panicking.
+//! "creation-backtrace" feature will capture backtrace when value is created instead.
+//! It is resolved only if value gets dropped.
 //! "message" feature will add custom message (specified when value was created) to the error.
+//! "id" feature gives every value unique id, printed in reports. See `Relevant::id`.
+//! "registry" feature keeps track of all live `Relevant` values. See `live` and `report_forgotten`.
+//! "suppress" feature allows silencing known leaks, see `Suppression`.
+//! "derive" feature enables `#[derive(Dispose)]` for types that contain `Relevant` fields
+//! and `#[relevant::dispose]` attribute that also marks type with `#[must_use]`.
+//! "test" feature enables `#[relevant::test]` attribute that fails test if a value
+//! is dropped on the test thread or created on it and left undisposed.
+//! Other threads are tracked while they are in the test's scope, see `TestScope`.
+//! "macros" feature enables `#[relevant::drop_handler]` attribute for "extern-handler" feature.
+//! "location" feature will add location where value was created to the error.
+//! "handle" feature will add raw handle of the resource to the error, see `Relevant::with_handle`.
 //!
 
 #![cfg_attr(not(feature = "std"), no_std)]
@@ -14,76 +56,411 @@
 #[cfg(not(feature = "std"))]
 use core as std;
 
+#[cfg(any(feature = "derive", feature = "macros"))]
+extern crate relevant_macros;
+
+#[cfg(feature = "suppress")]
+extern crate regex;
+
+#[cfg(feature = "config")]
+mod config;
+
+mod dispose;
+mod handler;
+mod info;
+mod must_dispose;
+mod policy;
+mod report;
+mod stats;
+mod sync;
+
+#[cfg(feature = "std")]
+mod capture;
+
+#[cfg(feature = "std")]
+mod limit;
+
+#[cfg(feature = "std")]
+mod unwind;
+
+#[cfg(feature = "registry")]
+mod registry;
+
+#[cfg(feature = "suppress")]
+mod suppress;
+
+#[cfg(feature = "test")]
+mod testing;
+
+#[cfg(all(
+    feature = "tracing",
+    not(feature = "panic"),
+    not(feature = "extern-handler")
+))]
+mod trace;
+
+pub use dispose::Dispose;
+pub use handler::{default_drop_handler, drop_handler, set_drop_handler, DropHandler};
+pub use must_dispose::MustDispose;
+pub use policy::{policy, set_policy, Policy};
+
+#[cfg(feature = "std")]
+pub use policy::set_category_policy;
+pub use report::DropReport;
+pub use stats::{stats, Stats};
+
+#[cfg(feature = "std")]
+pub use capture::capture;
+
+#[cfg(feature = "std")]
+pub use report::RelevantDropped;
+
+#[cfg(feature = "std")]
+pub use limit::{rate_limit, report_suppressed, set_rate_limit, RateLimit};
+
+#[cfg(feature = "std")]
+pub use unwind::{discarded_unwind_drops, report_unwind_drops, take_unwind_drops};
+
+#[cfg(feature = "registry")]
+pub use registry::{live, report_forgotten, Obligation};
+
+#[cfg(feature = "suppress")]
+pub use suppress::{add_suppression, clear_suppressions, Suppression};
+
+#[cfg(feature = "derive")]
+pub use relevant_macros::{dispose, Dispose};
+
+#[cfg(feature = "test")]
+pub use relevant_macros::test;
+
+#[cfg(feature = "test")]
+pub use testing::{TestScope, TestScopeGuard};
+
+#[cfg(feature = "macros")]
+pub use relevant_macros::drop_handler;
+
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "test")]
+    pub use testing::run_test;
+}
+
+use info::Info;
+use std::{
+    cmp::Ordering,
+    fmt::{Debug, Formatter},
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
 /// Values of this type can't be automatically dropped.
 /// If struct or enum has field with type `Relevant`,
 /// it can't be automatically dropped either. And so considered relevant too.
 /// User has to deconstruct such values and call `Relevant::dispose`.
 /// If relevant field is private it means that user has to move value into some public method.
 ///
+/// Type parameter is a category of the value.
+/// Its name is added to the error if value is dropped
+/// so that handlers and users can tell which kind of resource leaked.
+///
 /// # Panics
 ///
-/// With "panic" feature enabled this value will always panic on drop.
+/// With "panic" feature enabled this value will always panic on drop
+/// unless another drop handler is installed.
 ///
-#[derive(Clone, Debug, PartialOrd, PartialEq, Ord, Eq, Hash)]
-#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
-pub struct Relevant;
+pub struct Relevant<T: ?Sized = ()> {
+    info: Info,
+    marker: PhantomData<fn() -> T>,
+}
 
 impl Relevant {
+    /// Create new relevant value.
+    ///
+    /// With "location" feature the caller's location is recorded
+    /// and added to the error if value is dropped.
+    #[track_caller]
+    pub fn new() -> Self {
+        Relevant::from_info(Info::new())
+    }
+
+    /// Create new relevant value with message
+    /// that will be added to the error if value is dropped.
+    ///
+    /// Static strings are stored without allocation.
+    #[cfg(all(feature = "message", feature = "std"))]
+    #[track_caller]
+    pub fn with_message(message: impl Into<std::borrow::Cow<'static, str>>) -> Self {
+        let mut info = Info::new();
+        info.message = Some(message.into());
+        Relevant::from_info(info)
+    }
+
+    /// Create new relevant value with message
+    /// that will be added to the error if value is dropped.
+    #[cfg(all(feature = "message", not(feature = "std")))]
+    #[track_caller]
+    pub fn with_message(message: &'static str) -> Self {
+        let mut info = Info::new();
+        info.message = Some(message);
+        Relevant::from_info(info)
+    }
+
+    /// Create new relevant value for resource with raw handle
+    /// that will be added to the error if value is dropped.
+    ///
+    /// Handle allows cross-checking reports with logs of the resource owner, e.g. a driver.
+    #[cfg(feature = "handle")]
+    #[track_caller]
+    pub fn with_handle(handle: u64) -> Self {
+        let mut info = Info::new();
+        info.handle = Some(handle);
+        Relevant::from_info(info)
+    }
+
+    /// Create new relevant value with policy
+    /// that overrides global one set with `set_policy` or crate features.
+    ///
+    /// This allows library to decide how severe dropping its values is.
+    #[cfg(feature = "policy")]
+    #[track_caller]
+    pub fn with_policy(policy: Policy) -> Self {
+        let mut info = Info::new();
+        info.policy = Some(policy);
+        Relevant::from_info(info)
+    }
+}
+
+impl<T: ?Sized> Relevant<T> {
+    /// Create new relevant value of category `T`.
+    ///
+    /// `Relevant::new` and other constructors create values of the default `()` category,
+    /// so that type annotations are not required. Use `Relevant::tag` to change it.
+    #[track_caller]
+    pub fn tagged() -> Self {
+        Relevant::from_info(Info::new())
+    }
+
+    /// Change category of this value,
+    /// e.g. `Relevant::with_message("buffer must be returned").tag::<Buffer>()`.
+    pub fn tag<U: ?Sized>(self) -> Relevant<U> {
+        let this = std::mem::ManuallyDrop::new(self);
+        // `this` is not used after this point and `info` is not dropped twice.
+        let info = unsafe { std::ptr::read(&this.info) };
+        Relevant::from_info(info)
+    }
+
+    fn from_info(info: Info) -> Self {
+        #[cfg(feature = "registry")]
+        registry::register(&info, Self::category());
+
+        Relevant {
+            info,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns name of the category of this value.
+    pub fn category() -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    /// Override policy for this value.
+    #[cfg(feature = "policy")]
+    pub fn set_policy(&mut self, policy: Policy) {
+        self.info.policy = Some(policy);
+
+        #[cfg(feature = "registry")]
+        registry::set_policy(self.info.id, policy);
+    }
+
+    /// Returns unique id of this value.
+    ///
+    /// Ids are assigned in creation order, clones get new ids.
+    /// Use them to correlate logs, reports and obligations.
+    /// Note that ids don't participate in comparison and hashing.
+    #[cfg(feature = "id")]
+    pub fn id(&self) -> u64 {
+        self.info.id
+    }
+
+    /// Returns raw handle of the guarded resource.
+    #[cfg(feature = "handle")]
+    pub fn handle(&self) -> Option<u64> {
+        self.info.handle
+    }
+
+    /// Set raw handle of the guarded resource,
+    /// e.g. when it becomes known after this value was created.
+    #[cfg(feature = "handle")]
+    pub fn set_handle(&mut self, handle: u64) {
+        self.info.handle = Some(handle);
+
+        #[cfg(feature = "registry")]
+        registry::set_handle(self.info.id, handle);
+    }
+
+    /// Returns policy for this value.
+    pub fn policy(&self) -> Policy {
+        self.info.policy(Self::category())
+    }
+
+    /// Returns message specified when value was created.
+    #[cfg(feature = "message")]
+    pub fn message(&self) -> Option<&str> {
+        self.info.message.as_ref().map(|message| &message[..])
+    }
+
+    /// Returns location where value was created.
+    #[cfg(feature = "location")]
+    pub fn created_at(&self) -> &'static std::panic::Location<'static> {
+        self.info.location
+    }
+
     /// Dispose this value.
     pub fn dispose(self) {
-        std::mem::forget(self)
+        let mut this = std::mem::ManuallyDrop::new(self);
+        // `this` is not used after this point.
+        unsafe { std::ptr::drop_in_place(&mut this.info) }
+    }
+}
+
+impl<T: ?Sized> Clone for Relevant<T> {
+    /// Clone is a new obligation that must be disposed separately.
+    fn clone(&self) -> Self {
+        Relevant::from_info(self.info.clone())
+    }
+}
+
+impl<T: ?Sized> Debug for Relevant<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.debug_struct("Relevant")
+            .field("category", &Self::category())
+            .field("info", &self.info)
+            .finish()
+    }
+}
+
+impl<T: ?Sized> Default for Relevant<T> {
+    #[track_caller]
+    fn default() -> Self {
+        Relevant::tagged()
+    }
+}
+
+/// All `Relevant` values of the same category are equal to each other.
+impl<T: ?Sized> PartialEq for Relevant<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.info == other.info
+    }
+}
+
+impl<T: ?Sized> Eq for Relevant<T> {}
+
+impl<T: ?Sized> PartialOrd for Relevant<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: ?Sized> Ord for Relevant<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.info.cmp(&other.info)
+    }
+}
+
+impl<T: ?Sized> Hash for Relevant<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.info.hash(state)
     }
 }
 
-impl Drop for Relevant {
+impl<T: ?Sized> Drop for Relevant<T> {
     fn drop(&mut self) {
-        dropped()
+        dropped(&mut self.info, Self::category())
     }
 }
 
-cfg_if::cfg_if! {
-    if #[cfg(feature = "panic")] {
-        macro_rules! sink {
-            ($($x:tt)*) => { panic!($($x)*) };
-        }
-    } else if #[cfg(feature = "log")] {
-        macro_rules! sink {
-            ($($x:tt)*) => { log::error!($($x)*) };
-        }
-    } else if #[cfg(feature = "std")] {
-        macro_rules! sink {
-            ($($x:tt)*) => { eprintln!($($x)*) };
-        }
-    } else {
-        macro_rules! sink {
-            ($($x:tt)*) => { panic!($($x)*) };
-        }
+#[cfg(feature = "serde-1")]
+impl<T: ?Sized> serde::Serialize for Relevant<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_unit_struct("Relevant")
     }
 }
 
-cfg_if::cfg_if! {
-    if #[cfg(all(not(feature = "panic"), any(feature = "std", feature = "log"), feature = "backtrace"))] {
-        fn whine() {
-            let backtrace = backtrace::Backtrace::new();
-            sink!("Values of this type can't be dropped!. Trace: {:#?}", backtrace)
-        }
-    } else {
-        fn whine()  {
-            sink!("Values of this type can't be dropped!")
+#[cfg(feature = "serde-1")]
+impl<'de, T: ?Sized> serde::Deserialize<'de> for Relevant<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct Visitor<T: ?Sized>(PhantomData<fn() -> T>);
+
+        impl<'de, T: ?Sized> serde::de::Visitor<'de> for Visitor<T> {
+            type Value = Relevant<T>;
+
+            fn expecting(&self, fmt: &mut Formatter) -> std::fmt::Result {
+                fmt.write_str("unit struct Relevant")
+            }
+
+            fn visit_unit<E>(self) -> Result<Relevant<T>, E> {
+                Ok(Relevant::tagged())
+            }
         }
+
+        deserializer.deserialize_unit_struct("Relevant", Visitor(PhantomData))
     }
 }
 
-cfg_if::cfg_if! {
-    if #[cfg(feature = "std")] {
-        fn dropped() {
-            if !std::thread::panicking() {
-                whine()
+fn whine(info: &mut Info, category: &'static str) {
+    let policy = info.policy(category);
+    match policy {
+        Policy::Ignore => return,
+        // Tests that capture reports still get them.
+        Policy::Count if !handler::capturing() => return,
+        _ => {}
+    }
+
+    #[cfg(feature = "std")]
+    {
+        // Tests that capture reports get all of them.
+        if !handler::capturing() {
+            let admission = limit::admit(info, category, policy);
+            if let Some(summary) = admission.summary {
+                handler::dispatch(summary, policy);
             }
+            if !admission.report {
+                return;
+            }
+        }
+    }
+
+    handler::dispatch(DropReport::new(info, category), policy)
+}
+
+fn dropped(info: &mut Info, category: &'static str) {
+    stats::record(category);
+
+    #[cfg(feature = "suppress")]
+    {
+        if suppress::is_suppressed(info, category) {
+            return;
         }
-    } else {
-        fn dropped()  {
-            whine()
+    }
+
+    #[cfg(feature = "std")]
+    {
+        // Captured reports are collected right away, nothing is handled.
+        if std::thread::panicking() && !handler::capturing() {
+            match info.policy(category) {
+                Policy::Abort => {}
+                Policy::Count | Policy::Ignore => return,
+                policy => return unwind::defer(DropReport::new(info, category), policy),
+            }
         }
     }
-}
\ No newline at end of file
+
+    whine(info, category)
+}
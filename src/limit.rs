@@ -0,0 +1,334 @@
+use handler::{self, dispatch};
+use info::Info;
+use policy::Policy;
+use report::DropReport;
+use std::{
+    collections::BTreeMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use sync::lock;
+
+#[cfg(feature = "config")]
+use config;
+
+#[cfg(feature = "location")]
+use std::panic::Location;
+
+/// Limits number of reports for values created at the same site.
+///
+/// Only first `burst` values dropped within `window` are reported.
+/// The rest are counted and summarized in a single report
+/// when next value from the same site is reported or `report_suppressed` is called.
+///
+/// Site is the category of the value and, with "location" feature,
+/// location where value was created.
+/// Only `Policy::Report`, `Policy::Log` and `Policy::Eprint` are limited,
+/// and only if they don't end up panicking, e.g. with "panic" feature.
+/// Handlers installed with `set_drop_handler` are assumed to not panic.
+///
+/// Reports are not limited unless limit is set with `set_rate_limit`
+/// or, with "config" feature, in the config file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RateLimit {
+    /// Number of reports per window.
+    pub burst: usize,
+
+    /// Duration of the window.
+    pub window: Duration,
+}
+
+impl RateLimit {
+    /// Report every dropped value.
+    pub const UNLIMITED: RateLimit = RateLimit {
+        burst: usize::MAX,
+        window: Duration::from_secs(0),
+    };
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit::UNLIMITED
+    }
+}
+
+#[cfg(feature = "location")]
+type Site = (&'static str, &'static Location<'static>);
+
+#[cfg(not(feature = "location"))]
+type Site = &'static str;
+
+struct Window {
+    start: Instant,
+    reported: usize,
+    suppressed: usize,
+    policy: Policy,
+}
+
+struct State {
+    /// Not read from configuration until first use.
+    limit: Option<RateLimit>,
+    sites: BTreeMap<Site, Window>,
+}
+
+impl State {
+    const fn new() -> Self {
+        State {
+            limit: None,
+            sites: BTreeMap::new(),
+        }
+    }
+
+    fn limit(&mut self) -> RateLimit {
+        *self.limit.get_or_insert_with(configured)
+    }
+
+    fn admit(&mut self, site: Site, policy: Policy, now: Instant) -> Admission {
+        let limit = self.limit();
+        if limit == RateLimit::UNLIMITED {
+            return Admission {
+                summary: None,
+                report: true,
+            };
+        }
+
+        let window = self.sites.entry(site).or_insert(Window {
+            start: now,
+            reported: 0,
+            suppressed: 0,
+            policy,
+        });
+        window.policy = policy;
+
+        let mut suppressed = 0;
+        if now.duration_since(window.start) >= limit.window {
+            suppressed = std::mem::take(&mut window.suppressed);
+            window.start = now;
+            window.reported = 0;
+        }
+
+        let report = window.reported < limit.burst;
+        if report {
+            window.reported += 1;
+        } else {
+            window.suppressed += 1;
+        }
+
+        Admission {
+            summary: match suppressed {
+                0 => None,
+                suppressed => Some(summary(site, suppressed)),
+            },
+            report,
+        }
+    }
+
+    /// Take summaries of suppressed drops for all sites.
+    fn summaries(&mut self) -> Vec<(DropReport, Policy)> {
+        self.sites
+            .iter_mut()
+            .filter(|(_, window)| window.suppressed != 0)
+            .map(|(&site, window)| {
+                let suppressed = std::mem::take(&mut window.suppressed);
+                (summary(site, suppressed), window.policy)
+            })
+            .collect()
+    }
+}
+
+static STATE: Mutex<State> = Mutex::new(State::new());
+
+fn configured() -> RateLimit {
+    #[cfg(feature = "config")]
+    {
+        if let Some(limit) = config::config().rate_limit {
+            return limit;
+        }
+    }
+    RateLimit::UNLIMITED
+}
+
+/// Set limit for reports of dropped `Relevant` values.
+/// Returns previous limit.
+///
+/// Use `RateLimit::UNLIMITED` to report every value.
+pub fn set_rate_limit(limit: RateLimit) -> RateLimit {
+    let mut state = lock(&STATE);
+    let previous = state.limit();
+    state.limit = Some(limit);
+    previous
+}
+
+/// Returns current limit for reports of dropped `Relevant` values.
+///
+/// It is `RateLimit::UNLIMITED` unless changed with `set_rate_limit`
+/// or, with "config" feature, in the config file.
+pub fn rate_limit() -> RateLimit {
+    lock(&STATE).limit()
+}
+
+/// Report number of suppressed drops for each site
+/// and reset the counters. Returns number of suppressed drops.
+///
+/// Call this function before exit to not lose the tail of suppressed drops.
+pub fn report_suppressed() -> usize {
+    let summaries = lock(&STATE).summaries();
+
+    summaries
+        .into_iter()
+        .map(|(summary, policy)| {
+            let suppressed = summary.suppressed();
+            dispatch(summary, policy);
+            suppressed
+        })
+        .sum()
+}
+
+/// Decision on the dropped value.
+pub(crate) struct Admission {
+    /// Summary of drops from the same site suppressed in the previous window.
+    pub(crate) summary: Option<DropReport>,
+
+    /// Whether the value should be reported.
+    pub(crate) report: bool,
+}
+
+#[cfg_attr(not(feature = "location"), allow(unused_variables))]
+pub(crate) fn admit(info: &Info, category: &'static str, policy: Policy) -> Admission {
+    let limited = match policy {
+        Policy::Report => !handler::report_panics(),
+        // Without "log" feature report is passed to the drop handler.
+        Policy::Log => cfg!(feature = "log") || !handler::report_panics(),
+        Policy::Eprint => true,
+        _ => false,
+    };
+
+    if !limited {
+        return Admission {
+            summary: None,
+            report: true,
+        };
+    }
+
+    #[cfg(feature = "location")]
+    let site = (category, info.location);
+    #[cfg(not(feature = "location"))]
+    let site = category;
+
+    lock(&STATE).admit(site, policy, Instant::now())
+}
+
+#[cfg(feature = "location")]
+fn summary((category, location): Site, suppressed: usize) -> DropReport {
+    DropReport::summary(category, location, suppressed)
+}
+
+#[cfg(not(feature = "location"))]
+fn summary(category: Site, suppressed: usize) -> DropReport {
+    DropReport::summary(category, suppressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(burst: usize, window: Duration) -> State {
+        State {
+            limit: Some(RateLimit { burst, window }),
+            sites: BTreeMap::new(),
+        }
+    }
+
+    #[cfg(feature = "location")]
+    #[track_caller]
+    fn site() -> Site {
+        ("u8", Location::caller())
+    }
+
+    #[cfg(not(feature = "location"))]
+    fn site() -> Site {
+        "u8"
+    }
+
+    #[test]
+    fn reports_burst_and_suppresses_rest() {
+        let mut state = state(2, Duration::from_secs(1));
+        let site = site();
+        let now = Instant::now();
+
+        let reported: Vec<_> = (0..5)
+            .map(|_| state.admit(site, Policy::Report, now))
+            .map(|admission| {
+                assert!(admission.summary.is_none());
+                admission.report
+            })
+            .collect();
+
+        assert_eq!(reported, [true, true, false, false, false]);
+    }
+
+    #[test]
+    fn summarizes_on_window_rollover() {
+        let mut state = state(1, Duration::from_secs(1));
+        let site = site();
+        let now = Instant::now();
+
+        for _ in 0..4 {
+            state.admit(site, Policy::Report, now);
+        }
+
+        // Still within the window.
+        let admission = state.admit(site, Policy::Report, now + Duration::from_millis(500));
+        assert!(admission.summary.is_none());
+        assert!(!admission.report);
+
+        let admission = state.admit(site, Policy::Report, now + Duration::from_secs(1));
+        assert!(admission.report);
+        let summary = admission.summary.expect("summary of suppressed drops");
+        assert_eq!(summary.suppressed(), 4);
+        assert_eq!(summary.category(), "u8");
+        assert!(summary
+            .to_string()
+            .starts_with("u8: 4 further drops suppressed"));
+
+        // Counter is reset.
+        let admission = state.admit(site, Policy::Report, now + Duration::from_secs(2));
+        assert!(admission.summary.is_none());
+        assert!(admission.report);
+    }
+
+    #[test]
+    fn summaries_take_suppressed_drops() {
+        let mut state = state(1, Duration::from_secs(1));
+        let site = site();
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            state.admit(site, Policy::Eprint, now);
+        }
+
+        let summaries = state.summaries();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].0.suppressed(), 2);
+        assert_eq!(summaries[0].1, Policy::Eprint);
+        assert!(state.summaries().is_empty());
+    }
+
+    #[test]
+    fn unlimited_reports_everything() {
+        let mut state = state(0, Duration::from_secs(1));
+        state.limit = Some(RateLimit::UNLIMITED);
+        let site = site();
+        let now = Instant::now();
+
+        for _ in 0..100 {
+            assert!(state.admit(site, Policy::Report, now).report);
+        }
+        assert!(state.sites.is_empty());
+    }
+
+    #[test]
+    fn default_is_unlimited() {
+        assert_eq!(RateLimit::default(), RateLimit::UNLIMITED);
+    }
+}
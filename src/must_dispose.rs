@@ -0,0 +1,78 @@
+use std::ops::{Deref, DerefMut};
+use Relevant;
+
+/// Owns value of type `T` and can't be automatically dropped.
+///
+/// This is a shortcut for the common pattern of a struct
+/// with `Relevant` field next to the guarded value.
+/// Value can be taken back only with `MustDispose::into_inner`
+/// or `MustDispose::dispose_with`.
+/// Dropping `MustDispose` is reported the same way as dropping `Relevant`
+/// with `T` as category.
+#[derive(Clone, Debug, PartialOrd, PartialEq, Ord, Eq, Hash)]
+pub struct MustDispose<T> {
+    value: T,
+    relevant: Relevant<T>,
+}
+
+impl<T> MustDispose<T> {
+    /// Wrap value.
+    #[track_caller]
+    pub fn new(value: T) -> Self {
+        MustDispose {
+            value,
+            relevant: Relevant::tagged(),
+        }
+    }
+
+    /// Wrap value with message
+    /// that will be added to the error if wrapper is dropped.
+    #[cfg(all(feature = "message", feature = "std"))]
+    #[track_caller]
+    pub fn with_message(value: T, message: impl Into<std::borrow::Cow<'static, str>>) -> Self {
+        MustDispose {
+            value,
+            relevant: Relevant::with_message(message).tag(),
+        }
+    }
+
+    /// Wrap value with message
+    /// that will be added to the error if wrapper is dropped.
+    #[cfg(all(feature = "message", not(feature = "std")))]
+    #[track_caller]
+    pub fn with_message(value: T, message: &'static str) -> Self {
+        MustDispose {
+            value,
+            relevant: Relevant::with_message(message).tag(),
+        }
+    }
+
+    /// Dispose wrapper and return owned value.
+    pub fn into_inner(self) -> T {
+        let MustDispose { value, relevant } = self;
+        relevant.dispose();
+        value
+    }
+
+    /// Dispose wrapper passing owned value into the function.
+    pub fn dispose_with<F, R>(self, f: F) -> R
+    where
+        F: FnOnce(T) -> R,
+    {
+        f(self.into_inner())
+    }
+}
+
+impl<T> Deref for MustDispose<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for MustDispose<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
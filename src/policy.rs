@@ -0,0 +1,188 @@
+#[cfg(feature = "config")]
+use config;
+use std::sync::atomic::{AtomicU8, Ordering};
+use sync;
+
+#[cfg(feature = "std")]
+use std::{
+    any::type_name,
+    collections::BTreeMap,
+    sync::{atomic::AtomicBool, Mutex},
+};
+
+/// Defines what happens when `Relevant` value is dropped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Policy {
+    /// Build `DropReport` and pass it to the drop handler.
+    Report,
+
+    /// Only increment counters returned by `stats`.
+    /// No formatting or I/O is performed.
+    Count,
+
+    /// Pass `DropReport` to the drop handler and abort the process.
+    /// Values dropped while thread is panicking are reported too.
+    ///
+    /// Without "std" feature it panics after reporting.
+    Abort,
+
+    /// Panic, as with "panic" feature.
+    /// Values dropped while thread is panicking are reported later,
+    /// see `take_unwind_drops`.
+    Panic,
+
+    /// Emit `log::error!`, as with "log" feature.
+    /// Without "log" feature report is passed to the drop handler instead.
+    Log,
+
+    /// Print into stderr using `eprintln!`.
+    /// Without "std" feature report is passed to the drop handler instead.
+    Eprint,
+
+    /// Do nothing. Dropped values are still counted in `stats`,
+    /// but they are not reported even to `capture`.
+    Ignore,
+}
+
+impl Policy {
+    const fn into_u8(self) -> u8 {
+        match self {
+            Policy::Report => 0,
+            Policy::Count => 1,
+            Policy::Abort => 2,
+            Policy::Panic => 3,
+            Policy::Log => 4,
+            Policy::Eprint => 5,
+            Policy::Ignore => 6,
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Policy::Count,
+            2 => Policy::Abort,
+            3 => Policy::Panic,
+            4 => Policy::Log,
+            5 => Policy::Eprint,
+            6 => Policy::Ignore,
+            _ => Policy::Report,
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "abort")] {
+        const DEFAULT: Policy = Policy::Abort;
+    } else if #[cfg(feature = "count")] {
+        const DEFAULT: Policy = Policy::Count;
+    } else {
+        const DEFAULT: Policy = Policy::Report;
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "config")] {
+        /// Policy is not read from configuration yet.
+        const UNSET: u8 = u8::MAX;
+
+        static POLICY: AtomicU8 = AtomicU8::new(UNSET);
+
+        fn load() -> u8 {
+            let value = POLICY.load(Ordering::Relaxed);
+            if value != UNSET {
+                return value;
+            }
+            let initial = config::config().policy.unwrap_or(DEFAULT).into_u8();
+            match POLICY.compare_exchange(UNSET, initial, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => initial,
+                Err(value) => value,
+            }
+        }
+    } else {
+        static POLICY: AtomicU8 = AtomicU8::new(DEFAULT.into_u8());
+
+        fn load() -> u8 {
+            POLICY.load(Ordering::Relaxed)
+        }
+    }
+}
+
+/// Set policy for all `Relevant` values.
+/// Values created with `Relevant::with_policy` keep their own policy.
+/// Returns previous policy.
+pub fn set_policy(policy: Policy) -> Policy {
+    // Resolve configured policy first so it is returned as previous one.
+    load();
+    Policy::from_u8(sync::swap_u8(&POLICY, policy.into_u8()))
+}
+
+/// Returns current policy.
+///
+/// It is `Policy::Abort` with "abort" feature enabled,
+/// `Policy::Count` with "count" feature enabled
+/// and `Policy::Report` otherwise, unless changed with `set_policy`.
+///
+/// With "config" feature the default can be replaced with
+/// `RELEVANT_POLICY` environment variable or the config file.
+pub fn policy() -> Policy {
+    Policy::from_u8(load())
+}
+
+/// Returns policy for values of the category that don't have their own policy.
+/// Category policy takes precedence over global one.
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+pub(crate) fn resolve(category: &str) -> Policy {
+    #[cfg(feature = "std")]
+    {
+        if let Some(policy) = category_policy(category) {
+            return policy;
+        }
+    }
+
+    policy()
+}
+
+/// Policies set for categories with `set_category_policy`.
+#[cfg(feature = "std")]
+static CATEGORIES: Mutex<BTreeMap<&'static str, Policy>> = Mutex::new(BTreeMap::new());
+
+/// Allows skipping the lock while no category policies are set.
+#[cfg(feature = "std")]
+static HAS_CATEGORIES: AtomicBool = AtomicBool::new(false);
+
+/// Set policy for `Relevant<T>` values.
+/// `None` removes policy set for the category earlier.
+/// Returns previous policy set for the category.
+///
+/// Category policy takes precedence over global one.
+/// Values created with `Relevant::with_policy` keep their own policy.
+#[cfg(feature = "std")]
+pub fn set_category_policy<T: ?Sized>(policy: Option<Policy>) -> Option<Policy> {
+    let mut categories = sync::lock(&CATEGORIES);
+    let previous = match policy {
+        Some(policy) => categories.insert(type_name::<T>(), policy),
+        None => categories.remove(type_name::<T>()),
+    };
+    HAS_CATEGORIES.store(!categories.is_empty(), Ordering::Relaxed);
+    previous
+}
+
+/// Returns policy for the category set with `set_category_policy`
+/// or, with "config" feature, in the config file.
+#[cfg(feature = "std")]
+pub(crate) fn category_policy(category: &str) -> Option<Policy> {
+    if HAS_CATEGORIES.load(Ordering::Relaxed) {
+        if let Some(&policy) = sync::lock(&CATEGORIES).get(category) {
+            return Some(policy);
+        }
+    }
+
+    #[cfg(feature = "config")]
+    {
+        if let Some(policy) = config::config().category(category) {
+            return Some(policy);
+        }
+    }
+
+    None
+}
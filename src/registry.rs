@@ -0,0 +1,199 @@
+use handler::{self, dispatch};
+use info::Info;
+use policy::{self, Policy};
+use report::DropReport;
+use std::{
+    collections::BTreeMap,
+    panic::Location,
+    sync::Mutex,
+    thread::{self, Thread, ThreadId},
+    time::{Duration, Instant},
+};
+use sync::lock;
+
+#[cfg(feature = "message")]
+use info::Message;
+
+#[cfg(feature = "suppress")]
+use suppress;
+
+#[cfg(feature = "test")]
+use testing;
+
+/// Outstanding obligation - `Relevant` value that is neither disposed nor dropped yet.
+#[derive(Clone, Debug)]
+pub struct Obligation {
+    id: u64,
+    pub(crate) category: &'static str,
+    pub(crate) location: &'static Location<'static>,
+    #[cfg(feature = "message")]
+    pub(crate) message: Option<Message>,
+    #[cfg(feature = "handle")]
+    pub(crate) handle: Option<u64>,
+    #[cfg(feature = "policy")]
+    policy: Option<Policy>,
+    /// Scope of the test that created the value.
+    #[cfg(feature = "test")]
+    pub(crate) scope: Option<u64>,
+    thread: Thread,
+    created: Instant,
+}
+
+impl Obligation {
+    /// Unique id of the obligation.
+    /// Same as `Relevant::id` of the value.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Name of the category of the value.
+    pub fn category(&self) -> &'static str {
+        self.category
+    }
+
+    /// Location where value was created.
+    pub fn created_at(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// Message specified when value was created.
+    #[cfg(feature = "message")]
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_ref().map(|message| &message[..])
+    }
+
+    /// Raw handle of the guarded resource.
+    #[cfg(feature = "handle")]
+    pub fn handle(&self) -> Option<u64> {
+        self.handle
+    }
+
+    /// Policy for the value.
+    /// Same as `Relevant::policy` of the value.
+    pub fn policy(&self) -> Policy {
+        #[cfg(feature = "policy")]
+        {
+            if let Some(policy) = self.policy {
+                return policy;
+            }
+        }
+
+        policy::resolve(self.category)
+    }
+
+    /// Id of the thread on which value was created.
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread.id()
+    }
+
+    /// Name of the thread on which value was created.
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread.name()
+    }
+
+    /// Time passed since value was created.
+    pub fn age(&self) -> Duration {
+        self.created.elapsed()
+    }
+}
+
+static LIVE: Mutex<BTreeMap<u64, Obligation>> = Mutex::new(BTreeMap::new());
+
+/// Returns all outstanding obligations ordered by creation.
+///
+/// Call it at shutdown to find out what is still alive.
+pub fn live() -> Vec<Obligation> {
+    lock(&LIVE).values().cloned().collect()
+}
+
+/// Report all outstanding obligations as values that were neither disposed nor dropped,
+/// according to their policies, and forget about them.
+/// Returns reported obligations.
+/// With "suppress" feature obligations that match suppressions are forgotten without report.
+///
+/// `mem::forget` and reference cycles bypass `Relevant` drop.
+/// Call this function at shutdown, when no obligations are expected to be alive,
+/// to find values that were leaked this way.
+///
+/// Each obligation is forgotten once its report is handled, even if handling panics.
+/// Obligations that weren't reported yet stay in the registry.
+pub fn report_forgotten() -> Vec<Obligation> {
+    let mut reported = Vec::new();
+    for obligation in live() {
+        if !lock(&LIVE).contains_key(&obligation.id) {
+            // Disposed or dropped meanwhile.
+            continue;
+        }
+
+        // Forget obligation when its report is handled, including by panicking.
+        let _forget = Forget(obligation.id);
+
+        let report = DropReport::forgotten(&obligation);
+
+        #[cfg(feature = "suppress")]
+        {
+            if suppress::is_report_suppressed(&report) {
+                continue;
+            }
+        }
+
+        let policy = obligation.policy();
+        match policy {
+            Policy::Ignore => continue,
+            // Tests that capture reports still get them.
+            Policy::Count if !handler::capturing() => continue,
+            _ => {}
+        }
+
+        dispatch(report, policy);
+        reported.push(obligation);
+    }
+    reported
+}
+
+/// Removes obligation from the registry when dropped.
+struct Forget(u64);
+
+impl Drop for Forget {
+    fn drop(&mut self) {
+        unregister(self.0);
+    }
+}
+
+pub(crate) fn register(info: &Info, category: &'static str) {
+    let obligation = Obligation {
+        id: info.id,
+        category,
+        location: info.location,
+        #[cfg(feature = "message")]
+        message: info.message.clone(),
+        #[cfg(feature = "handle")]
+        handle: info.handle,
+        #[cfg(feature = "policy")]
+        policy: info.policy,
+        #[cfg(feature = "test")]
+        scope: testing::current_scope(),
+        thread: thread::current(),
+        created: Instant::now(),
+    };
+
+    lock(&LIVE).insert(info.id, obligation);
+}
+
+#[cfg(feature = "handle")]
+pub(crate) fn set_handle(id: u64, handle: u64) {
+    if let Some(obligation) = lock(&LIVE).get_mut(&id) {
+        obligation.handle = Some(handle);
+    }
+}
+
+#[cfg(feature = "policy")]
+pub(crate) fn set_policy(id: u64, policy: Policy) {
+    if let Some(obligation) = lock(&LIVE).get_mut(&id) {
+        obligation.policy = Some(policy);
+    }
+}
+
+pub(crate) fn unregister(id: u64) {
+    lock(&LIVE).remove(&id);
+}
@@ -0,0 +1,553 @@
+use info::Info;
+use std::{any::type_name, fmt};
+
+#[cfg(feature = "message")]
+use info::Message;
+
+#[cfg(feature = "location")]
+use std::panic::Location;
+
+#[cfg(all(feature = "config", feature = "backtrace"))]
+use config;
+
+#[cfg(feature = "registry")]
+use registry::Obligation;
+
+#[cfg(feature = "std")]
+use std::{
+    thread::{self, Thread, ThreadId},
+    time::SystemTime,
+};
+
+/// Describes a `Relevant` value that was dropped instead of being disposed,
+/// or, with "registry" feature, was forgotten.
+///
+/// Reports are built by the crate right before the installed drop handler is called.
+/// See `set_drop_handler` and `capture`.
+///
+/// There is no location of the drop, only of the creation.
+/// Values are dropped by compiler-generated drop glue that can't be `#[track_caller]`,
+/// so the drop site is available only through `backtrace` with "backtrace" feature.
+#[derive(Clone, Debug)]
+pub struct DropReport {
+    category: &'static str,
+    #[cfg(feature = "id")]
+    id: Option<u64>,
+    #[cfg(feature = "message")]
+    message: Option<Message>,
+    #[cfg(feature = "handle")]
+    handle: Option<u64>,
+    #[cfg(feature = "location")]
+    location: &'static Location<'static>,
+    #[cfg(feature = "creation-backtrace")]
+    creation_backtrace: Option<backtrace::Backtrace>,
+    #[cfg(feature = "backtrace")]
+    backtrace: Option<backtrace::Backtrace>,
+    #[cfg(feature = "std")]
+    thread: Thread,
+    #[cfg(feature = "std")]
+    timestamp: SystemTime,
+    #[cfg(feature = "std")]
+    panicking: bool,
+    #[cfg(feature = "registry")]
+    forgotten: bool,
+    #[cfg(feature = "std")]
+    suppressed: usize,
+    #[cfg(feature = "tracing")]
+    span: Option<tracing::Span>,
+}
+
+impl DropReport {
+    #[cfg_attr(
+        not(any(
+            feature = "id",
+            feature = "message",
+            feature = "handle",
+            feature = "location",
+            feature = "creation-backtrace",
+            feature = "tracing"
+        )),
+        allow(unused_variables)
+    )]
+    pub(crate) fn new(info: &mut Info, category: &'static str) -> Self {
+        DropReport {
+            category,
+            #[cfg(feature = "id")]
+            id: Some(info.id),
+            #[cfg(feature = "message")]
+            message: info.message.take(),
+            #[cfg(feature = "handle")]
+            handle: info.handle,
+            #[cfg(feature = "location")]
+            location: info.location,
+            #[cfg(feature = "creation-backtrace")]
+            creation_backtrace: info.backtrace.take().map(|mut backtrace| {
+                // Symbols are resolved only now that value is actually dropped.
+                backtrace.resolve();
+                backtrace
+            }),
+            #[cfg(feature = "backtrace")]
+            backtrace: capture_backtrace(),
+            #[cfg(feature = "std")]
+            thread: thread::current(),
+            #[cfg(feature = "std")]
+            timestamp: SystemTime::now(),
+            #[cfg(feature = "std")]
+            panicking: thread::panicking(),
+            #[cfg(feature = "registry")]
+            forgotten: false,
+            #[cfg(feature = "std")]
+            suppressed: 0,
+            #[cfg(feature = "tracing")]
+            span: info.span.take(),
+        }
+    }
+
+    /// Summary of drops from the same site that were not reported due to `RateLimit`.
+    #[cfg(feature = "std")]
+    pub(crate) fn summary(
+        category: &'static str,
+        #[cfg(feature = "location")] location: &'static Location<'static>,
+        suppressed: usize,
+    ) -> Self {
+        DropReport {
+            category,
+            #[cfg(feature = "id")]
+            id: None,
+            #[cfg(feature = "message")]
+            message: None,
+            #[cfg(feature = "handle")]
+            handle: None,
+            #[cfg(feature = "location")]
+            location,
+            #[cfg(feature = "creation-backtrace")]
+            creation_backtrace: None,
+            #[cfg(feature = "backtrace")]
+            backtrace: None,
+            thread: thread::current(),
+            timestamp: SystemTime::now(),
+            panicking: thread::panicking(),
+            #[cfg(feature = "registry")]
+            forgotten: false,
+            suppressed,
+            #[cfg(feature = "tracing")]
+            span: None,
+        }
+    }
+
+    /// Report for value that was neither disposed nor dropped.
+    #[cfg(feature = "registry")]
+    pub(crate) fn forgotten(obligation: &Obligation) -> Self {
+        DropReport {
+            category: obligation.category,
+            id: Some(obligation.id()),
+            #[cfg(feature = "message")]
+            message: obligation.message.clone(),
+            #[cfg(feature = "handle")]
+            handle: obligation.handle,
+            location: obligation.location,
+            #[cfg(feature = "creation-backtrace")]
+            creation_backtrace: None,
+            #[cfg(feature = "backtrace")]
+            backtrace: None,
+            thread: thread::current(),
+            timestamp: SystemTime::now(),
+            panicking: thread::panicking(),
+            forgotten: true,
+            suppressed: 0,
+            #[cfg(feature = "tracing")]
+            span: None,
+        }
+    }
+
+    /// Name of the category of the value.
+    /// That is the type parameter of `Relevant`.
+    pub fn category(&self) -> &'static str {
+        self.category
+    }
+
+    /// Unique id of the value, see `Relevant::id`.
+    /// `None` for summaries of suppressed drops.
+    #[cfg(feature = "id")]
+    pub fn id(&self) -> Option<u64> {
+        self.id
+    }
+
+    /// Message specified when value was created.
+    #[cfg(feature = "message")]
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_ref().map(|message| &message[..])
+    }
+
+    /// Location where value was created.
+    #[cfg(feature = "location")]
+    pub fn created_at(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// Raw handle of the resource, see `Relevant::with_handle`.
+    #[cfg(feature = "handle")]
+    pub fn handle(&self) -> Option<u64> {
+        self.handle
+    }
+
+    /// Backtrace captured when value was created.
+    #[cfg(feature = "creation-backtrace")]
+    pub fn creation_backtrace(&self) -> Option<&backtrace::Backtrace> {
+        self.creation_backtrace.as_ref()
+    }
+
+    /// Backtrace captured when value was dropped.
+    /// It points to the place where value was dropped:
+    /// frames below `Drop` implementation of `Relevant` and `drop_in_place` belong to the drop site.
+    #[cfg(feature = "backtrace")]
+    pub fn backtrace(&self) -> Option<&backtrace::Backtrace> {
+        self.backtrace.as_ref()
+    }
+
+    /// Id of the thread on which value was dropped.
+    /// For forgotten values it is the thread that reported them.
+    #[cfg(feature = "std")]
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread.id()
+    }
+
+    /// Name of the thread on which value was dropped.
+    /// For forgotten values it is the thread that reported them.
+    #[cfg(feature = "std")]
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread.name()
+    }
+
+    /// Time when report was made.
+    #[cfg(feature = "std")]
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// Returns `true` if value was dropped while thread was panicking.
+    #[cfg(feature = "std")]
+    pub fn panicking(&self) -> bool {
+        self.panicking
+    }
+
+    /// Returns `true` if value was neither disposed nor dropped.
+    /// Such values are reported by `report_forgotten`.
+    #[cfg(feature = "registry")]
+    pub fn is_forgotten(&self) -> bool {
+        self.forgotten
+    }
+
+    /// Number of drops this report summarizes.
+    /// Non-zero for summaries of drops suppressed by `RateLimit`, zero otherwise.
+    #[cfg(feature = "std")]
+    pub fn suppressed(&self) -> usize {
+        self.suppressed
+    }
+
+    /// Span that was active when value was created.
+    #[cfg(feature = "tracing")]
+    pub fn span(&self) -> Option<&tracing::Span> {
+        self.span.as_ref()
+    }
+
+    #[cfg(feature = "registry")]
+    fn header(&self) -> &'static str {
+        if self.forgotten {
+            "Values of this type can't be forgotten!"
+        } else {
+            "Values of this type can't be dropped!"
+        }
+    }
+
+    #[cfg(not(feature = "registry"))]
+    fn header(&self) -> &'static str {
+        "Values of this type can't be dropped!"
+    }
+}
+
+impl fmt::Display for DropReport {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if self.category != type_name::<()>() {
+            write!(fmt, "{}: ", self.category)?;
+        }
+
+        #[cfg(feature = "std")]
+        {
+            if self.suppressed != 0 {
+                write!(fmt, "{} further drops suppressed", self.suppressed)?;
+            } else {
+                fmt.write_str(self.header())?;
+            }
+        }
+
+        #[cfg(not(feature = "std"))]
+        fmt.write_str(self.header())?;
+
+        #[cfg(feature = "message")]
+        {
+            if let Some(message) = &self.message {
+                write!(fmt, " {}", message)?;
+            }
+        }
+
+        #[cfg(feature = "location")]
+        write!(fmt, " (created at {})", self.location)?;
+
+        #[cfg(feature = "handle")]
+        {
+            if let Some(handle) = self.handle {
+                write!(fmt, " (handle {:#x})", handle)?;
+            }
+        }
+
+        #[cfg(feature = "id")]
+        {
+            if let Some(id) = self.id {
+                write!(fmt, " [id {}]", id)?;
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        {
+            if let Some(metadata) = self.span.as_ref().and_then(tracing::Span::metadata) {
+                write!(fmt, " in span `{}`", metadata.name())?;
+            }
+        }
+
+        #[cfg(feature = "std")]
+        {
+            match self.thread.name() {
+                Some(name) => write!(fmt, " [thread '{}'", name)?,
+                None => write!(fmt, " [thread {:?}", self.thread.id())?,
+            }
+            if self.panicking {
+                fmt.write_str(", dropped during panic")?;
+            }
+            fmt.write_str("]")?;
+        }
+
+        #[cfg(feature = "creation-backtrace")]
+        {
+            if let Some(backtrace) = &self.creation_backtrace {
+                write!(fmt, ". Creation trace: {:#?}", backtrace)?;
+            }
+        }
+
+        #[cfg(feature = "backtrace")]
+        {
+            if let Some(backtrace) = &self.backtrace {
+                write!(fmt, ". Trace: {:#?}", backtrace)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Payload of the panic raised by `default_drop_handler` with "panic" feature
+/// and for values with `Policy::Panic`.
+///
+/// `catch_unwind` callers can downcast the payload to this type
+/// to tell a dropped `Relevant` value from any other panic.
+///
+/// Default panic hook prints only string payloads, and the report is not printed anywhere else.
+/// Install a panic hook that downcasts the payload to show the report:
+///
+/// ```
+/// std::panic::set_hook(Box::new(|info| {
+///     match info.payload().downcast_ref::<relevant::RelevantDropped>() {
+///         Some(dropped) => eprintln!("{}", dropped),
+///         None => eprintln!("{}", info),
+///     }
+/// }));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct RelevantDropped {
+    report: DropReport,
+}
+
+#[cfg(feature = "std")]
+impl RelevantDropped {
+    /// Wrap report.
+    /// Custom drop handlers can use it to panic the same way as default one.
+    pub fn new(report: DropReport) -> Self {
+        RelevantDropped { report }
+    }
+
+    /// Report for the dropped value.
+    pub fn report(&self) -> &DropReport {
+        &self.report
+    }
+
+    /// Take report for the dropped value.
+    pub fn into_report(self) -> DropReport {
+        self.report
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for RelevantDropped {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.report, fmt)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RelevantDropped {}
+
+/// Compact representation for "defmt" logging.
+/// Constant parts are interned strings and are not transmitted.
+#[cfg(feature = "defmt")]
+impl defmt::Format for DropReport {
+    fn format(&self, fmt: defmt::Formatter) {
+        if self.category != type_name::<()>() {
+            defmt::write!(fmt, "{=str}: ", self.category);
+        }
+
+        #[cfg(feature = "std")]
+        {
+            if self.suppressed != 0 {
+                defmt::write!(fmt, "{=usize} further drops suppressed", self.suppressed);
+                #[cfg(feature = "location")]
+                defmt::write!(
+                    fmt,
+                    " (created at {=str}:{=u32}:{=u32})",
+                    self.location.file(),
+                    self.location.line(),
+                    self.location.column(),
+                );
+                return;
+            }
+        }
+
+        // Interned strings look the same to clippy.
+        #[cfg(feature = "registry")]
+        #[allow(clippy::if_same_then_else)]
+        {
+            if self.forgotten {
+                defmt::write!(fmt, "Values of this type can't be forgotten!");
+            } else {
+                defmt::write!(fmt, "Values of this type can't be dropped!");
+            }
+        }
+
+        #[cfg(not(feature = "registry"))]
+        defmt::write!(fmt, "Values of this type can't be dropped!");
+
+        #[cfg(feature = "message")]
+        {
+            if let Some(message) = self.message() {
+                defmt::write!(fmt, " {=str}", message);
+            }
+        }
+
+        #[cfg(feature = "location")]
+        defmt::write!(
+            fmt,
+            " (created at {=str}:{=u32}:{=u32})",
+            self.location.file(),
+            self.location.line(),
+            self.location.column(),
+        );
+
+        #[cfg(feature = "handle")]
+        {
+            if let Some(handle) = self.handle {
+                defmt::write!(fmt, " (handle {=u64:#x})", handle);
+            }
+        }
+
+        #[cfg(feature = "id")]
+        {
+            if let Some(id) = self.id {
+                defmt::write!(fmt, " [id {=u64}]", id);
+            }
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "creation-backtrace")] {
+        // Creation trace points to the origin of the value. No need to capture another one.
+        fn default_backtrace() -> Option<backtrace::Backtrace> {
+            None
+        }
+    } else if #[cfg(all(feature = "backtrace", not(feature = "panic"), any(feature = "std", feature = "log")))] {
+        fn default_backtrace() -> Option<backtrace::Backtrace> {
+            Some(backtrace::Backtrace::new())
+        }
+    } else if #[cfg(feature = "backtrace")] {
+        fn default_backtrace() -> Option<backtrace::Backtrace> {
+            None
+        }
+    }
+}
+
+/// Capture drop backtrace if configured or selected by features.
+#[cfg(feature = "backtrace")]
+fn capture_backtrace() -> Option<backtrace::Backtrace> {
+    #[cfg(feature = "config")]
+    {
+        match config::config().backtrace {
+            None => {}
+            Some(config::Backtrace::Drop) => return Some(backtrace::Backtrace::new()),
+            // Without "creation-backtrace" drop trace is the best we have.
+            Some(config::Backtrace::Creation) if cfg!(not(feature = "creation-backtrace")) => {
+                return Some(backtrace::Backtrace::new())
+            }
+            Some(_) => return None,
+        }
+    }
+    default_backtrace()
+}
+
+#[cfg(all(test, feature = "defmt", feature = "std"))]
+mod tests {
+    use super::*;
+    use info::Info;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    };
+
+    /// Host logger that collects frames instead of sending them to a probe.
+    #[defmt::global_logger]
+    struct Logger;
+
+    static TAKEN: AtomicBool = AtomicBool::new(false);
+    static BYTES: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+    unsafe impl defmt::Logger for Logger {
+        fn acquire() {
+            while TAKEN.swap(true, Ordering::Acquire) {
+                std::hint::spin_loop();
+            }
+        }
+
+        unsafe fn flush() {}
+
+        unsafe fn release() {
+            TAKEN.store(false, Ordering::Release);
+        }
+
+        unsafe fn write(bytes: &[u8]) {
+            BYTES.lock().unwrap().extend_from_slice(bytes);
+        }
+    }
+
+    defmt::timestamp!("");
+
+    #[test]
+    fn formats_with_defmt() {
+        let report = DropReport::new(&mut Info::new(), "defmt_category");
+        defmt::error!("{}", report);
+
+        let bytes = std::mem::take(&mut *BYTES.lock().unwrap());
+        // Interned strings are transmitted as indices, `{=str}` arguments verbatim.
+        assert!(bytes
+            .windows("defmt_category".len())
+            .any(|window| window == b"defmt_category"));
+    }
+}
@@ -0,0 +1,175 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use sync;
+
+#[cfg(feature = "std")]
+use std::{
+    collections::BTreeMap,
+    ptr,
+    sync::{atomic::AtomicPtr, Mutex},
+};
+
+/// Counters of dropped `Relevant` values.
+/// Returned by `stats`.
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    total: usize,
+    #[cfg(feature = "std")]
+    categories: BTreeMap<&'static str, usize>,
+}
+
+impl Stats {
+    /// Total number of dropped values.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Number of dropped values of the category.
+    #[cfg(feature = "std")]
+    pub fn category(&self, category: &str) -> usize {
+        self.categories.get(category).copied().unwrap_or(0)
+    }
+
+    /// Iterate over categories and number of dropped values of each category.
+    #[cfg(feature = "std")]
+    pub fn categories(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        self.categories
+            .iter()
+            .map(|(&category, &count)| (category, count))
+    }
+}
+
+static TOTAL: AtomicUsize = AtomicUsize::new(0);
+
+/// Counter of dropped values of one category.
+#[cfg(feature = "std")]
+struct Entry {
+    category: &'static str,
+    count: AtomicUsize,
+}
+
+/// Number of categories counted without locking.
+#[cfg(feature = "std")]
+const SLOTS: usize = 256;
+
+/// Open addressing table of leaked entries.
+/// Entries are never removed, so counting a drop is a lookup and an atomic increment.
+#[cfg(feature = "std")]
+static TABLE: [AtomicPtr<Entry>; SLOTS] = [const { AtomicPtr::new(ptr::null_mut()) }; SLOTS];
+
+/// Categories that didn't fit into the table.
+#[cfg(feature = "std")]
+static OVERFLOW: Mutex<BTreeMap<&'static str, usize>> = Mutex::new(BTreeMap::new());
+
+/// Returns counters of dropped `Relevant` values.
+///
+/// Every dropped value is counted regardless of `Policy`.
+/// Per category counters are available with "std" feature.
+pub fn stats() -> Stats {
+    Stats {
+        total: TOTAL.load(Ordering::Relaxed),
+        #[cfg(feature = "std")]
+        categories: categories(),
+    }
+}
+
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+pub(crate) fn record(category: &'static str) {
+    sync::increment(&TOTAL);
+
+    #[cfg(feature = "std")]
+    {
+        match entry(category) {
+            Some(entry) => {
+                entry.count.fetch_add(1, Ordering::Relaxed);
+            }
+            None => *sync::lock(&OVERFLOW).entry(category).or_insert(0) += 1,
+        }
+    }
+}
+
+/// Find or insert entry for the category.
+/// Returns `None` if the table is full.
+#[cfg(feature = "std")]
+fn entry(category: &'static str) -> Option<&'static Entry> {
+    let start = hash(category);
+    for index in 0..SLOTS {
+        let slot = &TABLE[(start + index) % SLOTS];
+        let mut current = slot.load(Ordering::Acquire);
+
+        if current.is_null() {
+            let new = Box::into_raw(Box::new(Entry {
+                category,
+                count: AtomicUsize::new(0),
+            }));
+            match slot.compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire) {
+                // Entry is leaked and lives until the end of the program.
+                Ok(_) => return Some(unsafe { &*new }),
+                Err(other) => {
+                    // Another thread took the slot first.
+                    drop(unsafe { Box::from_raw(new) });
+                    current = other;
+                }
+            }
+        }
+
+        // Non-null slots always point to leaked entries.
+        let entry = unsafe { &*current };
+
+        // Same type may have distinct names in different codegen units, compare content.
+        if entry.category == category {
+            return Some(entry);
+        }
+    }
+    None
+}
+
+/// FNV-1a hash of the category.
+#[cfg(feature = "std")]
+fn hash(category: &str) -> usize {
+    let hash = category.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    hash as usize % SLOTS
+}
+
+#[cfg(feature = "std")]
+fn categories() -> BTreeMap<&'static str, usize> {
+    let mut categories = sync::lock(&OVERFLOW).clone();
+    for slot in &TABLE {
+        let entry = slot.load(Ordering::Acquire);
+        if !entry.is_null() {
+            // Non-null slots always point to leaked entries.
+            let entry = unsafe { &*entry };
+            *categories.entry(entry.category).or_insert(0) += entry.count.load(Ordering::Relaxed);
+        }
+    }
+    categories
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_categories() {
+        let category = "stats::tests::counts_categories";
+        // Same name at different address.
+        let copy: &'static str = Box::leak(category.to_owned().into_boxed_str());
+
+        let before = stats();
+        record(category);
+        record(copy);
+        record(category);
+        let after = stats();
+
+        assert!(after.total() >= before.total() + 3);
+        assert_eq!(after.category(category), 3);
+        assert_eq!(
+            after
+                .categories()
+                .filter(|&(name, _)| name == category)
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use info::Info;
+use regex::Regex;
+use report::DropReport;
+use std::{panic::Location, sync::Mutex};
+use sync::lock;
+
+#[cfg(feature = "config")]
+use config;
+
+/// Pattern for known leaks.
+///
+/// Dropped values that match any added suppression are counted in `stats`
+/// but not reported. Suppression matches value if all its patterns match.
+/// Suppression without patterns matches every value.
+///
+/// With "config" feature suppressions are also read from `[[suppress]]` tables of the config file.
+///
+/// ```toml
+/// [[suppress]]
+/// category = "third_party::*"
+/// location = "*/third_party/src/*:*"
+/// message = "^texture"
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Suppression {
+    category: Option<String>,
+    location: Option<String>,
+    message: Option<Regex>,
+}
+
+impl Suppression {
+    /// Suppression that matches every value.
+    /// Restrict it with other methods.
+    pub fn new() -> Self {
+        Suppression::default()
+    }
+
+    /// Match category of the value with glob pattern.
+    /// `*` matches any sequence of characters and `?` matches one character.
+    pub fn category(mut self, pattern: impl Into<String>) -> Self {
+        self.category = Some(pattern.into());
+        self
+    }
+
+    /// Match location where value was created, formatted as `file:line`, with glob pattern.
+    /// `*` matches any sequence of characters, including path separators,
+    /// and `?` matches one character.
+    pub fn location(mut self, pattern: impl Into<String>) -> Self {
+        self.location = Some(pattern.into());
+        self
+    }
+
+    /// Match message of the value with regular expression.
+    /// Values without message never match.
+    pub fn message(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.message = Some(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    fn matches(&self, category: &str, location: &Location, message: Option<&str>) -> bool {
+        if let Some(pattern) = &self.category {
+            if !glob(pattern, category) {
+                return false;
+            }
+        }
+
+        if let Some(pattern) = &self.location {
+            let location = format!("{}:{}", location.file(), location.line());
+            if !glob(pattern, &location) {
+                return false;
+            }
+        }
+
+        if let Some(regex) = &self.message {
+            if !message.is_some_and(|message| regex.is_match(message)) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Not read from configuration until first use.
+static SUPPRESSIONS: Mutex<Option<Vec<Suppression>>> = Mutex::new(None);
+
+/// Add suppression for known leaks.
+pub fn add_suppression(suppression: Suppression) {
+    lock(&SUPPRESSIONS)
+        .get_or_insert_with(configured)
+        .push(suppression)
+}
+
+/// Remove all suppressions, including ones read from the config file.
+pub fn clear_suppressions() {
+    *lock(&SUPPRESSIONS) = Some(Vec::new())
+}
+
+/// Returns `true` if dropped value must not be reported.
+pub(crate) fn is_suppressed(info: &Info, category: &str) -> bool {
+    #[cfg(feature = "message")]
+    let message = info.message.as_ref().map(|message| &message[..]);
+    #[cfg(not(feature = "message"))]
+    let message = None;
+
+    suppressed(category, info.location, message)
+}
+
+/// Returns `true` if report must not be passed to the drop handler.
+/// Used for reports that are not dispatched right on drop.
+pub(crate) fn is_report_suppressed(report: &DropReport) -> bool {
+    #[cfg(feature = "message")]
+    let message = report.message();
+    #[cfg(not(feature = "message"))]
+    let message = None;
+
+    suppressed(report.category(), report.created_at(), message)
+}
+
+fn suppressed(category: &str, location: &Location, message: Option<&str>) -> bool {
+    lock(&SUPPRESSIONS)
+        .get_or_insert_with(configured)
+        .iter()
+        .any(|suppression| suppression.matches(category, location, message))
+}
+
+fn configured() -> Vec<Suppression> {
+    #[cfg(feature = "config")]
+    {
+        config::config().suppressions.clone()
+    }
+
+    #[cfg(not(feature = "config"))]
+    {
+        Vec::new()
+    }
+}
+
+/// Glob matching where `*` matches any sequence and `?` matches single character.
+fn glob(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of last `*` in pattern and text position it was tried at.
+    let mut star = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    // Let last `*` consume one more character.
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches() {
+        assert!(glob("", ""));
+        assert!(glob("abc", "abc"));
+        assert!(!glob("abc", "abd"));
+        assert!(!glob("abc", "ab"));
+        assert!(!glob("ab", "abc"));
+
+        assert!(glob("*", ""));
+        assert!(glob("*", "anything"));
+        assert!(glob("a*", "a"));
+        assert!(glob("a*c", "abbbc"));
+        assert!(!glob("a*c", "abbbd"));
+
+        assert!(glob("a?c", "abc"));
+        assert!(!glob("a?c", "ac"));
+        assert!(glob("?", "\u{e9}"));
+    }
+
+    #[test]
+    fn glob_backtracks() {
+        // First `c` is not the end, `*` must consume more.
+        assert!(glob("a*c", "acbc"));
+        assert!(glob("*ab", "aab"));
+        assert!(glob("*a*b*c", "xaybzbc"));
+        assert!(glob("src/*/lib.rs:*", "src/a/b/lib.rs:10"));
+        assert!(!glob("*a*b", "bbba"));
+        assert!(!glob("a*?", "a"));
+    }
+
+    #[test]
+    fn suppression_matches() {
+        let location = Location::caller();
+        let line = format!("*:{}", location.line());
+
+        assert!(Suppression::new().matches("u8", location, None));
+
+        let category = Suppression::new().category("third_party::*");
+        assert!(category.matches("third_party::Texture", location, None));
+        assert!(!category.matches("my::Texture", location, None));
+
+        assert!(Suppression::new()
+            .location(line.clone())
+            .matches("u8", location, None));
+        assert!(!Suppression::new()
+            .location("*/other.rs:*")
+            .matches("u8", location, None));
+
+        let message = Suppression::new().message("^texture").unwrap();
+        assert!(message.matches("u8", location, Some("texture #1")));
+        assert!(!message.matches("u8", location, Some("buffer #1")));
+        // Values without message never match message pattern.
+        assert!(!message.matches("u8", location, None));
+
+        // All patterns must match.
+        let all = Suppression::new()
+            .category("u8")
+            .location(line)
+            .message("^texture")
+            .unwrap();
+        assert!(all.matches("u8", location, Some("texture")));
+        assert!(!all.matches("u16", location, Some("texture")));
+        assert!(!all.matches("u8", location, Some("buffer")));
+
+        assert!(Suppression::new().message("(").is_err());
+    }
+}
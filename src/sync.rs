@@ -0,0 +1,58 @@
+use std::sync::atomic::{AtomicPtr, AtomicU8, AtomicUsize, Ordering};
+
+#[cfg(feature = "std")]
+use std::sync::{Mutex, MutexGuard};
+
+cfg_if::cfg_if! {
+    if #[cfg(target_has_atomic = "ptr")] {
+        /// Store pointer and return previous one.
+        pub(crate) fn swap_ptr<T>(atomic: &AtomicPtr<T>, ptr: *mut T) -> *mut T {
+            atomic.swap(ptr, Ordering::AcqRel)
+        }
+
+        /// Store value and return previous one.
+        pub(crate) fn swap_u8(atomic: &AtomicU8, value: u8) -> u8 {
+            atomic.swap(value, Ordering::Relaxed)
+        }
+
+        /// Increment counter and return previous value.
+        pub(crate) fn increment(atomic: &AtomicUsize) -> usize {
+            atomic.fetch_add(1, Ordering::Relaxed)
+        }
+    } else {
+        // No compare-and-swap on targets like thumbv6m, only loads and stores.
+        // Update is lost if interrupted by another one, e.g. in an interrupt handler.
+        // Handlers and policies are expected to be set during initialization there,
+        // and counters and ids are best effort.
+
+        /// Store pointer and return previous one.
+        pub(crate) fn swap_ptr<T>(atomic: &AtomicPtr<T>, ptr: *mut T) -> *mut T {
+            let old = atomic.load(Ordering::Acquire);
+            atomic.store(ptr, Ordering::Release);
+            old
+        }
+
+        /// Store value and return previous one.
+        pub(crate) fn swap_u8(atomic: &AtomicU8, value: u8) -> u8 {
+            let old = atomic.load(Ordering::Relaxed);
+            atomic.store(value, Ordering::Relaxed);
+            old
+        }
+
+        /// Increment counter and return previous value.
+        pub(crate) fn increment(atomic: &AtomicUsize) -> usize {
+            let old = atomic.load(Ordering::Relaxed);
+            atomic.store(old.wrapping_add(1), Ordering::Relaxed);
+            old
+        }
+    }
+}
+
+/// Lock mutex ignoring poisoning.
+///
+/// Data behind the crate's mutexes is never left in inconsistent state,
+/// and a panicking drop handler must not break reporting for the rest of the program.
+#[cfg(feature = "std")]
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
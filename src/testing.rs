@@ -0,0 +1,158 @@
+use capture::{self, Restore};
+use registry;
+use report::DropReport;
+use std::{
+    cell::RefCell,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use sync::lock;
+
+/// Scope of the test run with `#[relevant::test]`.
+///
+/// Only the test thread is tracked by default.
+/// Threads spawned by the test are tracked while they are in the test's scope.
+///
+/// ```ignore
+/// #[relevant::test]
+/// fn spawns() {
+///     let scope = relevant::TestScope::current().unwrap();
+///     std::thread::spawn(move || {
+///         let _scope = scope.enter();
+///         Relevant::new().dispose();
+///     })
+///     .join()
+///     .unwrap();
+/// }
+/// ```
+///
+/// Threads must leave the scope before test body returns,
+/// values dropped or created after that are not tracked.
+#[derive(Clone, Debug)]
+pub struct TestScope {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    id: u64,
+    /// Reports of values dropped in the scope.
+    reports: Mutex<Vec<DropReport>>,
+}
+
+static NEXT_SCOPE: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static CURRENT: RefCell<Option<TestScope>> = const { RefCell::new(None) };
+}
+
+impl TestScope {
+    fn new() -> Self {
+        TestScope {
+            shared: Arc::new(Shared {
+                id: NEXT_SCOPE.fetch_add(1, Ordering::Relaxed),
+                reports: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Returns scope of the test running on this thread.
+    /// Returns `None` outside of tests and threads that didn't enter test's scope.
+    pub fn current() -> Option<TestScope> {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+
+    /// Enter the scope on this thread, until returned guard is dropped.
+    ///
+    /// Values created on this thread meanwhile must be disposed before the test returns,
+    /// and values dropped meanwhile fail the test.
+    pub fn enter(&self) -> TestScopeGuard {
+        let previous = CURRENT.with(|current| current.replace(Some(self.clone())));
+        TestScopeGuard {
+            scope: self.clone(),
+            previous: Some(previous),
+            capture: capture::start(),
+        }
+    }
+}
+
+/// Keeps thread in the test's scope. See `TestScope::enter`.
+#[must_use = "thread leaves the scope when guard is dropped"]
+pub struct TestScopeGuard {
+    scope: TestScope,
+    previous: Option<Option<TestScope>>,
+    capture: Restore,
+}
+
+impl Drop for TestScopeGuard {
+    fn drop(&mut self) {
+        let dropped = self.capture.finish();
+        lock(&self.scope.shared.reports).extend(dropped);
+
+        let previous = self.previous.take().and_then(|previous| previous);
+        let _ = CURRENT.try_with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Returns id of the test scope this thread is in.
+pub(crate) fn current_scope() -> Option<u64> {
+    CURRENT
+        .try_with(|current| current.borrow().as_ref().map(|scope| scope.shared.id))
+        .ok()
+        .and_then(|id| id)
+}
+
+/// Runs test body and panics if any `Relevant` value leaked.
+/// Used by `#[relevant::test]` attribute.
+///
+/// Only the test thread and threads that entered `TestScope` are tracked.
+pub fn run_test<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let scope = TestScope::new();
+
+    let guard = scope.enter();
+    let result = f();
+    drop(guard);
+
+    let mut failure = String::new();
+
+    let dropped = std::mem::take(&mut *lock(&scope.shared.reports));
+    if !dropped.is_empty() {
+        let _ = writeln!(failure, "{} relevant value(s) dropped:", dropped.len());
+        for report in &dropped {
+            let _ = writeln!(failure, "  {}", report);
+        }
+    }
+
+    let alive: Vec<_> = registry::live()
+        .into_iter()
+        .filter(|obligation| obligation.scope == Some(scope.shared.id))
+        .collect();
+
+    if !alive.is_empty() {
+        let _ = writeln!(failure, "{} relevant value(s) not disposed:", alive.len());
+        for obligation in &alive {
+            let _ = writeln!(
+                failure,
+                "  #{} {} (created at {})",
+                obligation.id(),
+                obligation.category(),
+                obligation.created_at(),
+            );
+
+            // Already reported by this test, don't leave them for `report_forgotten`.
+            registry::unregister(obligation.id());
+        }
+    }
+
+    if !failure.is_empty() {
+        panic!("Test leaked relevant values\n{}", failure);
+    }
+
+    result
+}
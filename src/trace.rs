@@ -0,0 +1,75 @@
+use report::DropReport;
+use std::panic::Location;
+
+/// Emit `tracing::error!` event for the report.
+///
+/// Event is emitted within the span that was active when value was created, if any.
+/// Otherwise it gets contextual parent, as any other event.
+pub(crate) fn emit(report: &DropReport) {
+    let created_at = created_at(report).map(tracing::field::display);
+    // `message` field name is reserved for event's message.
+    let note = message(report);
+    let id = id(report);
+    let handle = handle(report);
+
+    macro_rules! error {
+        ($($parent:tt)*) => {
+            tracing::error!(
+                target: "relevant",
+                $($parent)*
+                category = report.category(),
+                id,
+                handle,
+                note,
+                created_at,
+                "{}",
+                report,
+            )
+        };
+    }
+
+    match report.span().and_then(tracing::Span::id) {
+        Some(id) => error!(parent: id,),
+        None => error!(),
+    }
+}
+
+#[cfg(feature = "id")]
+fn id(report: &DropReport) -> Option<u64> {
+    report.id()
+}
+
+#[cfg(not(feature = "id"))]
+fn id(_: &DropReport) -> Option<u64> {
+    None
+}
+
+#[cfg(feature = "handle")]
+fn handle(report: &DropReport) -> Option<u64> {
+    report.handle()
+}
+
+#[cfg(not(feature = "handle"))]
+fn handle(_: &DropReport) -> Option<u64> {
+    None
+}
+
+#[cfg(feature = "message")]
+fn message(report: &DropReport) -> Option<&str> {
+    report.message()
+}
+
+#[cfg(not(feature = "message"))]
+fn message(_: &DropReport) -> Option<&str> {
+    None
+}
+
+#[cfg(feature = "location")]
+fn created_at(report: &DropReport) -> Option<&'static Location<'static>> {
+    Some(report.created_at())
+}
+
+#[cfg(not(feature = "location"))]
+fn created_at(_: &DropReport) -> Option<&'static Location<'static>> {
+    None
+}
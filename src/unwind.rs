@@ -0,0 +1,105 @@
+use handler::dispatch;
+use policy::Policy;
+use report::DropReport;
+use std::{collections::VecDeque, sync::Mutex};
+use sync::lock;
+
+#[cfg(feature = "suppress")]
+use suppress;
+
+/// Maximum number of queued reports.
+/// Processes that catch panics and never take the reports must not grow without bound.
+const CAPACITY: usize = 64;
+
+struct Deferred {
+    /// Reports with policies of dropped values.
+    reports: VecDeque<(DropReport, Policy)>,
+
+    /// Number of oldest reports discarded to keep queue bounded.
+    discarded: usize,
+}
+
+static DEFERRED: Mutex<Deferred> = Mutex::new(Deferred {
+    reports: VecDeque::new(),
+    discarded: 0,
+});
+
+/// Take reports for `Relevant` values dropped while their thread was panicking.
+///
+/// Such values are not passed to the drop handler immediately,
+/// as panicking again would abort the process.
+/// Call this function after `catch_unwind` returns or after joining panicked thread.
+///
+/// Only last 64 reports are kept, see `discarded_unwind_drops`.
+pub fn take_unwind_drops() -> Vec<DropReport> {
+    lock(&DEFERRED)
+        .reports
+        .drain(..)
+        .map(|(report, _)| report)
+        .collect()
+}
+
+/// Handle reports for `Relevant` values dropped while their thread was panicking
+/// according to the policy of each value. Returns number of reports.
+///
+/// Reports of values with `Policy::Panic` make this function panic.
+/// Remaining reports stay queued.
+/// With "suppress" feature reports that match suppressions are skipped.
+///
+/// See `take_unwind_drops`.
+pub fn report_unwind_drops() -> usize {
+    let mut count = 0;
+    loop {
+        let next = lock(&DEFERRED).reports.pop_front();
+        match next {
+            Some((report, policy)) => {
+                // Suppression may be added after the value was dropped.
+                #[cfg(feature = "suppress")]
+                {
+                    if suppress::is_report_suppressed(&report) {
+                        continue;
+                    }
+                }
+
+                count += 1;
+                dispatch(report, policy);
+            }
+            None => return count,
+        }
+    }
+}
+
+/// Returns number of reports discarded since last call,
+/// because more than 64 values were dropped during panics
+/// without reports being taken.
+/// Discarded values are still counted in `stats`.
+pub fn discarded_unwind_drops() -> usize {
+    std::mem::take(&mut lock(&DEFERRED).discarded)
+}
+
+pub(crate) fn defer(report: DropReport, policy: Policy) {
+    let mut deferred = lock(&DEFERRED);
+    if deferred.reports.len() == CAPACITY {
+        deferred.reports.pop_front();
+        deferred.discarded += 1;
+    }
+    deferred.reports.push_back((report, policy));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use info::Info;
+
+    #[test]
+    fn keeps_last_reports() {
+        for _ in 0..CAPACITY + 6 {
+            defer(DropReport::new(&mut Info::new(), "u8"), Policy::Eprint);
+        }
+
+        assert_eq!(lock(&DEFERRED).reports[0].1, Policy::Eprint);
+        assert_eq!(take_unwind_drops().len(), CAPACITY);
+        assert_eq!(discarded_unwind_drops(), 6);
+        assert_eq!(discarded_unwind_drops(), 0);
+    }
+}
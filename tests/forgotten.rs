@@ -0,0 +1,97 @@
+#![cfg(feature = "registry")]
+
+extern crate relevant;
+
+use relevant::{DropReport, Policy, Relevant};
+use std::{
+    panic::catch_unwind,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+/// Registry, policy and drop handler are global.
+static LOCK: Mutex<()> = Mutex::new(());
+
+static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+fn lock() -> MutexGuard<'static, ()> {
+    let guard = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    relevant::set_drop_handler(count);
+    relevant::set_policy(Policy::Report);
+    HANDLED.store(0, Ordering::Relaxed);
+    guard
+}
+
+fn count(report: &DropReport) {
+    assert!(report.is_forgotten());
+    HANDLED.fetch_add(1, Ordering::Relaxed);
+}
+
+fn panic(_: &DropReport) {
+    panic!("handler failed");
+}
+
+#[test]
+fn reports_forgotten() {
+    let _lock = lock();
+    std::mem::forget(Relevant::new());
+    std::mem::forget(Relevant::new());
+    Relevant::new().dispose();
+
+    assert_eq!(relevant::report_forgotten().len(), 2);
+    assert_eq!(HANDLED.load(Ordering::Relaxed), 2);
+    assert!(relevant::live().is_empty());
+    assert!(relevant::report_forgotten().is_empty());
+}
+
+#[test]
+fn panicking_handler_keeps_rest() {
+    let _lock = lock();
+    for _ in 0..3 {
+        std::mem::forget(Relevant::new());
+    }
+
+    relevant::set_drop_handler(panic);
+    assert!(catch_unwind(relevant::report_forgotten).is_err());
+
+    // Obligation that was reported is forgotten, the rest are not.
+    assert_eq!(relevant::live().len(), 2);
+
+    relevant::set_drop_handler(count);
+    assert_eq!(relevant::report_forgotten().len(), 2);
+    assert_eq!(HANDLED.load(Ordering::Relaxed), 2);
+    assert!(relevant::live().is_empty());
+}
+
+#[test]
+fn respects_policies() {
+    let _lock = lock();
+    relevant::set_policy(Policy::Ignore);
+    std::mem::forget(Relevant::new());
+
+    relevant::set_category_policy::<u8>(Some(Policy::Report));
+    std::mem::forget(Relevant::<u8>::tagged());
+
+    #[cfg(feature = "policy")]
+    {
+        let mut value = Relevant::<u8>::tagged();
+        value.set_policy(Policy::Count);
+        std::mem::forget(value);
+        std::mem::forget(Relevant::with_policy(Policy::Report));
+    }
+
+    let reported = relevant::report_forgotten();
+
+    let expected = if cfg!(feature = "policy") { 2 } else { 1 };
+    assert_eq!(reported.len(), expected);
+    assert_eq!(HANDLED.load(Ordering::Relaxed), expected);
+    assert!(reported
+        .iter()
+        .all(|obligation| obligation.policy() == Policy::Report));
+    relevant::set_category_policy::<u8>(None);
+
+    // Ignored values are forgotten as well.
+    assert!(relevant::live().is_empty());
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "policy")]
+
+extern crate relevant;
+
+use relevant::{capture, set_category_policy, set_policy, Policy, Relevant};
+
+struct Tag;
+
+/// Global policy is shared, so all cases run in a single test.
+#[test]
+fn value_policy_takes_precedence() {
+    set_policy(Policy::Ignore);
+    assert_eq!(Relevant::new().policy(), Policy::Ignore);
+
+    // Value's own policy beats global one.
+    let ((), reports) = capture(|| {
+        let relevant = Relevant::with_policy(Policy::Count);
+        assert_eq!(relevant.policy(), Policy::Count);
+    });
+    assert_eq!(reports.len(), 1);
+
+    // Category policy beats global one.
+    set_policy(Policy::Count);
+    set_category_policy::<Tag>(Some(Policy::Ignore));
+    let ((), reports) = capture(|| {
+        let relevant = Relevant::<Tag>::tagged();
+        assert_eq!(relevant.policy(), Policy::Ignore);
+    });
+    assert!(reports.is_empty());
+
+    // Value's own policy beats category one, also when set after creation.
+    let ((), reports) = capture(|| {
+        let mut relevant = Relevant::<Tag>::tagged();
+        relevant.set_policy(Policy::Report);
+        assert_eq!(relevant.policy(), Policy::Report);
+    });
+    assert_eq!(reports.len(), 1);
+
+    let ((), reports) = capture(|| {
+        let relevant = Relevant::with_policy(Policy::Ignore).tag::<Tag>();
+        assert_eq!(relevant.policy(), Policy::Ignore);
+        set_category_policy::<Tag>(Some(Policy::Report));
+    });
+    assert!(reports.is_empty());
+
+    set_category_policy::<Tag>(None);
+    set_policy(Policy::Report);
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "test")]
+
+extern crate relevant;
+
+use relevant::{Relevant, TestScope};
+use std::{panic::catch_unwind, thread};
+
+#[relevant::test]
+fn clean() {
+    let relevant = Relevant::new();
+    relevant.dispose();
+}
+
+#[relevant::test]
+#[should_panic(expected = "1 relevant value(s) dropped")]
+fn fails_on_dropped() {
+    let _relevant = Relevant::new();
+}
+
+#[relevant::test]
+#[should_panic(expected = "1 relevant value(s) not disposed")]
+fn fails_on_forgotten() {
+    std::mem::forget(Relevant::new());
+}
+
+#[relevant::test]
+fn returns_result() -> Result<(), String> {
+    let relevant = Relevant::new();
+    relevant.dispose();
+    "42".parse::<u32>().map(drop).map_err(|err| err.to_string())
+}
+
+#[relevant::test]
+#[should_panic(expected = "test body")]
+fn panics_with_body_message() {
+    let _relevant = Relevant::new();
+    panic!("test body");
+}
+
+#[relevant::test]
+#[should_panic(expected = "1 relevant value(s) dropped")]
+fn fails_on_dropped_during_caught_panic() {
+    let _ = catch_unwind(|| {
+        let _relevant = Relevant::new();
+        panic!("caught");
+    });
+}
+
+#[relevant::test]
+#[should_panic(expected = "1 relevant value(s) dropped")]
+fn fails_on_dropped_in_scoped_thread() {
+    let scope = TestScope::current().unwrap();
+    thread::spawn(move || {
+        let _scope = scope.enter();
+        let _relevant = Relevant::new();
+    })
+    .join()
+    .unwrap();
+}
+
+#[relevant::test]
+#[should_panic(expected = "1 relevant value(s) not disposed")]
+fn fails_on_forgotten_in_scoped_thread() {
+    let scope = TestScope::current().unwrap();
+    thread::spawn(move || {
+        let _scope = scope.enter();
+        std::mem::forget(Relevant::new());
+    })
+    .join()
+    .unwrap();
+}
+
+#[relevant::test]
+fn passes_values_between_scoped_threads() {
+    let scope = TestScope::current().unwrap();
+    let relevant = thread::spawn(move || {
+        let _scope = scope.enter();
+        Relevant::new()
+    })
+    .join()
+    .unwrap();
+    relevant.dispose();
+}
+
+#[test]
+fn no_scope_outside_tests() {
+    assert!(TestScope::current().is_none());
+}